@@ -1,9 +1,11 @@
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::fmt::Write;
 
 use protoc_gen_constructors::prelude::*;
-use protoc_gen_constructors::documentation::write_javadoc;
+use protoc_gen_constructors::documentation::{write_javadoc, write_overload_javadoc};
+use protoc_gen_constructors::type_check::ValueType;
 
 use inflector::cases::camelcase::to_camel_case;
 use inflector::cases::pascalcase::to_pascal_case;
@@ -16,30 +18,37 @@ fn run(
     context : &PluginContext
 ) -> Result<CodeGeneratorResponse, GeneratorError>
 {
+    // The stand-alone functions are implemented by hand so ensure they exist before generating
+    // any code that calls them.
+    check_helper_calls( context )?;
+
     // We'll need to return a CodeGeneratorResponse to protoc as the plugin response.
     let mut response = CodeGeneratorResponse::new();
 
+    // In the standalone mode the constructors are placed in classes of their own.
+    if is_standalone( context )? {
+        for ( type_context, type_spec ) in context.iter_generated_types() {
+            if ! type_spec.constructors.is_empty() {
+                response.mut_file().push( get_constructors_file( &type_context, type_spec )? );
+            }
+        }
+        return Ok( response );
+    }
+
     // Java generator uses insertion points to inject the functions into the types. The responses
     // we give back to protoc just define code for these insertion points for each type.
 
     for ( type_context, ctor ) in context.iter_generated_constructors() {
 
-        // We need to tell protoc which file the insertion point is in.  For this reason we'll
-        // need to mirror the output path of the original java generator.
-        //
-        // This output path uses the java package (net.foo.bar) as path (net/foo/bar) and the
-        // final file name is based on the outer class name.
-        let file_descriptor = &type_context.file_context.descriptor;
-        let output_path = format!( "{}/{}.java",
-                get_java_package( &file_descriptor ).replace(".", "/"),
-                get_outer_class( &file_descriptor ) );
+        // We need to tell protoc which file the insertion point is in.
+        let output_path = get_output_path( &type_context );
 
         // Generate the constructor code.
         //
         // We'll generate a constructor for the builder (which has the setters) and a
         // constructor for the class (which just delegates to the builder).
-        let builder_code = get_builder_ctor( &type_context, ctor )?;
-        let class_code = get_class_ctor( &type_context, ctor )?;
+        let builder_code = get_builder_ctor( &type_context, ctor, false )?;
+        let class_code = get_class_ctor( &type_context, ctor, false )?;
 
         // Add both of the constructors as responses to the total response object.
 
@@ -61,73 +70,466 @@ fn run(
     Ok( response )
 }
 
-/// Creates the constructor defined on the `Builder` class.
+/// Gets the mode of the generator from the `java_mode` plugin parameter.
+///
+/// Returns `true` for the standalone mode and `false` for the default insertion point mode.
+fn is_standalone(
+    context : &PluginContext
+) -> Result<bool, GeneratorError>
+{
+    match context.get_option( "java_mode" ) {
+        None | Some( "insertion" ) => Ok( false ),
+        Some( "standalone" ) => Ok( true ),
+        Some( other ) => Err( format!( "Unknown java_mode `{}`", other ).into() ),
+    }
+}
+
+/// Creates the `<Message>Constructors.java` file for the standalone mode.
 ///
-/// This is the constructor that does the actual field setting.
-fn get_builder_ctor( 
+/// The file contains a class with the constructors of a single message. These use the builders
+/// of the protoc generated classes so nothing needs to be inserted into the generated code.
+fn get_constructors_file(
     type_context : &TypeContext,
-    ctor : &spec::Constructor
+    type_spec : &spec::Type
+) -> Result<CodeGeneratorResponse_File, GeneratorError>
+{
+    let package = get_java_package( type_context.file_context.descriptor );
+    let class_name = get_constructors_class_name( type_context );
+
+    let mut result = String::new();
+    let mut out = IndentingWriter::new( &mut result, "  " );
+    if ! package.is_empty() {
+        writeln!( out, "package {};", package )?;
+        writeln!( out )?;
+    }
+    writeln!( out, "/**" )?;
+    writeln!( out, " * Constructors for {{@link {}}}.", get_java_class( type_context ) )?;
+    writeln!( out, " */" )?;
+    writeln!( out, "public final class {}", class_name )?;
+    writeln!( out, "{{" )?;
+    out.indent();
+    writeln!( out, "private {}() {{}}", class_name )?;
+    for ctor in &type_spec.constructors {
+        writeln!( out )?;
+        write!( out, "{}", get_builder_ctor( type_context, ctor, true )? )?;
+        write!( out, "{}", get_class_ctor( type_context, ctor, true )? )?;
+    }
+    out.unindent();
+    writeln!( out, "}}" )?;
+
+    let mut file = CodeGeneratorResponse_File::new();
+    file.set_name( if package.is_empty() {
+        format!( "{}.java", class_name )
+    } else {
+        format!( "{}/{}.java", package.replace( ".", "/" ), class_name )
+    } );
+    file.set_content( result );
+    Ok( file )
+}
+
+/// Gets the name of the standalone mode constructor class.
+///
+/// The names of the nested types are concatenated with the names of their parents.
+fn get_constructors_class_name(
+    type_context : &TypeContext
+) -> String
+{
+    format!( "{}Constructors", type_context.get_rel_name().replace( ".", "" ) )
+}
+
+/// Gets the fully qualified name of the class that defines the constructors of the type.
+fn get_constructors_class(
+    type_context : &TypeContext
 ) -> Result<String, GeneratorError>
 {
-    // Format the constructor name and parameter list.
-    //
-    // Both the constructor and parameter names are in camel case in Java.
-    let ctor_name = to_camel_case( ctor.name );
-    let param_list = utils::join(
-        &ctor.params, ", ", |p| format!( "{} {}",
-           get_type( &p.param_type ),
-           to_camel_case( &p.name ) ) );
+    if ! is_standalone( &type_context.file_context.plugin_context )? {
+        return Ok( get_java_class( type_context ) );
+    }
+
+    let package = get_java_package( type_context.file_context.descriptor );
+    let class_name = get_constructors_class_name( type_context );
+    Ok( if package.is_empty() {
+        class_name
+    } else {
+        format!( "{}.{}", package, class_name )
+    } )
+}
+
+/// Signature of a generated Java method used for generating its overloads.
+struct Method
+{
+    /// Modifiers and the return type, such as `public static Builder`.
+    prefix : String,
+
+    /// Method name.
+    name : String,
+
+    /// Type of the `_builder` parameter that precedes the constructor parameters if any.
+    builder_param : Option<String>,
+}
+
+/// Creates the method that applies the constructor initializers to an existing builder.
+///
+/// By default this is an instance method on the `Builder` class. In the standalone mode there is
+/// no access to the `Builder` class so the builder is given as the first parameter instead.
+fn get_apply_method(
+    type_context : &TypeContext,
+    ctor : &spec::Constructor,
+    standalone : bool
+) -> Result<String, GeneratorError>
+{
+    let method = if standalone {
+        let builder_type = format!( "{}.Builder", get_java_class( type_context ) );
+        Method {
+            prefix: format!( "public static {}", builder_type ),
+            name: get_apply_name( ctor.name ),
+            builder_param: Some( builder_type ),
+        }
+    } else {
+        Method {
+            prefix: "public Builder".to_string(),
+            name: get_apply_name( ctor.name ),
+            builder_param: None,
+        }
+    };
+    let target = if standalone { "_builder" } else { "this" };
 
     let mut out = String::new();
     write_javadoc( &mut out, ctor )?;
-    writeln!( out, "public static Builder {}({}) {{", ctor_name, param_list )?;
-    writeln!( out, "  Builder _builder = new Builder();" )?;
+    write_nonnull_annotation( &mut out, type_context )?;
+    writeln!( out, "{} {}({}) {{",
+        method.prefix, method.name, get_method_params( type_context, &method, &ctor.params ) )?;
+
+    // Protobuf setters fail on null values as well but the resulting exception doesn't tell which
+    // parameter was null. All the other constructors delegate here so this is the only place that
+    // needs the checks.
+    if standalone {
+        writeln!( out, "  java.util.Objects.requireNonNull(_builder, \"_builder\");" )?;
+    }
+    for param in ctor.params.iter().filter( |p| is_reference_type( &p.param_type ) ) {
+        let param_name = to_camel_case( param.name );
+        writeln!( out, "  java.util.Objects.requireNonNull({}, \"{}\");", param_name, param_name )?;
+    }
 
     for initializer in &ctor.initializers
     {
+        // Map fields are set through the `putAll` method that accepts a map and repeated fields
+        // through the `addAll` method that accepts any iterable.
+        let setter = if type_context.get_map_entry( initializer.field ).is_some() {
+            "putAll"
+        } else if type_context.is_repeated_field( initializer.field ) {
+            "addAll"
+        } else {
+            "set"
+        };
+
         // For the setters we'll use pascal casing because the initial `set` takes care of the
         // lower case portion of the camel casing.
-        writeln!( out, "  _builder.set{}({});",
+        writeln!( out, "  {}.{}{}({});",
+            target,
+            setter,
             to_pascal_case( initializer.field ),
-            get_value( type_context, &initializer.value ),
+            get_value(
+                type_context,
+                &initializer.value,
+                type_context.get_field( initializer.field )
+                    .map( |f| ValueType::of_field( type_context, f ) )
+                    .as_ref() )?,
         )?;
     }
 
-    writeln!( out, "  return _builder;" )?;
+    writeln!( out, "  return {};", target )?;
+    writeln!( out, "}}" )?;
+
+    out.push_str( &get_default_overloads( type_context, ctor, &method )? );
+
+    Ok(out)
+}
+
+/// Creates the constructor that returns a builder.
+///
+/// The constructor creates a new builder and applies the initializers on it. By default the
+/// constructor is defined on the `Builder` class. In the standalone mode it uses the
+/// `newBuilder()` of the message and gets a `Builder` suffix to distinguish it from the message
+/// constructor.
+///
+/// The apply method the constructor delegates to is included in the result.
+fn get_builder_ctor(
+    type_context : &TypeContext,
+    ctor : &spec::Constructor,
+    standalone : bool
+) -> Result<String, GeneratorError>
+{
+    // Format the constructor name and parameter list.
+    //
+    // Both the constructor and parameter names are in camel case in Java.
+    let apply_name = get_apply_name( ctor.name );
+    let arg_list = utils::join( &ctor.params, ", ", |p| to_camel_case( p.name ) );
+    let ( method, body ) = if standalone {
+        let class_name = get_java_class( type_context );
+        let method = Method {
+            prefix: format!( "public static {}.Builder", class_name ),
+            name: get_builder_ctor_name( ctor.name ),
+            builder_param: None,
+        };
+        let body = if arg_list.is_empty() {
+            format!( "{}({}.newBuilder())", apply_name, class_name )
+        } else {
+            format!( "{}({}.newBuilder(), {})", apply_name, class_name, arg_list )
+        };
+        ( method, body )
+    } else {
+        let method = Method {
+            prefix: "public static Builder".to_string(),
+            name: get_ctor_name( ctor.name ),
+            builder_param: None,
+        };
+        ( method, format!( "new Builder().{}({})", apply_name, arg_list ) )
+    };
+
+    let mut out = get_apply_method( type_context, ctor, standalone )?;
+    write_javadoc( &mut out, ctor )?;
+    write_nonnull_annotation( &mut out, type_context )?;
+    writeln!( out, "{} {}({}) {{",
+        method.prefix, method.name, get_method_params( type_context, &method, &ctor.params ) )?;
+    writeln!( out, "  return {};", body )?;
     writeln!( out, "}}" )?;
 
+    out.push_str( &get_default_overloads( type_context, ctor, &method )? );
+
     Ok(out)
 }
 
-/// Creates the constructor defined on the message class.
+/// Creates the constructor that returns the message.
 ///
 /// The constructor on the message just delegates to the Builder constructor.
-fn get_class_ctor( 
+fn get_class_ctor(
     type_context : &TypeContext,
-    ctor : &spec::Constructor
+    ctor : &spec::Constructor,
+    standalone : bool
 ) -> Result<String, GeneratorError>
 {
     // Class names in Java are in pascal case, everything else here is camel case.
-    let class_name = to_pascal_case( type_context.get_name() );
-    let ctor_name = to_camel_case( ctor.name );
-    let param_list = utils::join( &ctor.params, ", ", |p| format!( "{} {}",
-                   get_type( &p.param_type ),
-                   to_camel_case( &p.name ) ) );
-    let arg_list = utils::join( &ctor.params, ", ", |p| format!( "{}",
-                   to_camel_case( &p.name ) ) );
+    //
+    // The builder is an inner class so it's even named the same for all classes. In the
+    // standalone mode the builder constructor is defined next to this one.
+    let ( class_name, builder_ctor ) = if standalone {
+        ( get_java_class( type_context ), get_builder_ctor_name( ctor.name ) )
+    } else {
+        (
+            to_pascal_case( type_context.get_name() ),
+            format!( "Builder.{}", get_ctor_name( ctor.name ) ),
+        )
+    };
+    let method = Method {
+        prefix: format!( "public static {}", class_name ),
+        name: get_ctor_name( ctor.name ),
+        builder_param: None,
+    };
+    let arg_list = utils::join( &ctor.params, ", ", |p| to_camel_case( p.name ) );
 
     // The class constructor is a simple delegation to the builder constructor and then invoking
-    // `.build()` on the received builder. The builder is an inner class so it's even named the
-    // same for all classes.
+    // `.build()` on the received builder.
     let mut out = String::new();
     write_javadoc( &mut out, ctor )?;
-    writeln!( out, "public static {} {}({}) {{", class_name, ctor_name, param_list )?;
-    writeln!( out, "  return Builder.{}({}).build();", ctor_name, arg_list )?;
+    write_nonnull_annotation( &mut out, type_context )?;
+    writeln!( out, "{} {}({}) {{",
+        method.prefix, method.name, get_method_params( type_context, &method, &ctor.params ) )?;
+    writeln!( out, "  return {}({}).build();", builder_ctor, arg_list )?;
     writeln!( out, "}}" )?;
 
+    out.push_str( &get_default_overloads( type_context, ctor, &method )? );
+
+    Ok(out)
+}
+
+/// Creates the overloads that omit the trailing parameters that have default values.
+///
+/// Java has no default arguments so each overload delegates to the full method with the
+/// default values in place of the omitted arguments.
+fn get_default_overloads(
+    type_context : &TypeContext,
+    ctor : &spec::Constructor,
+    method : &Method,
+) -> Result<String, GeneratorError>
+{
+    let required_count = ctor.params.iter().take_while( |p| p.default.is_none() ).count();
+
+    let mut out = String::new();
+    for param_count in required_count .. ctor.params.len() {
+
+        let ( params, omitted ) = ctor.params.split_at( param_count );
+        let arg_list = method.builder_param.iter()
+            .map( |_| Ok( "_builder".to_string() ) )
+            .chain( params.iter().map( |p| Ok( to_camel_case( p.name ) ) ) )
+            .chain( omitted.iter()
+                .filter_map( |p| p.default.as_ref().map( |d| ( p, d ) ) )
+                .map( |( p, d )| get_value(
+                        type_context, d,
+                        ValueType::of_param( type_context, &p.param_type ).as_ref() ) ) )
+            .collect::<Result<Vec<_>, GeneratorError>>()?
+            .join( ", " );
+
+        write_overload_javadoc( &mut out, ctor, param_count )?;
+        write_nonnull_annotation( &mut out, type_context )?;
+        writeln!( out, "{} {}({}) {{",
+            method.prefix, method.name, get_method_params( type_context, method, params ) )?;
+        writeln!( out, "  return {}({});", method.name, arg_list )?;
+        writeln!( out, "}}" )?;
+    }
+
     Ok(out)
 }
 
+/// Formats the parameter list of a method including the `_builder` parameter if it has one.
+fn get_method_params(
+    type_context : &TypeContext,
+    method : &Method,
+    params : &[spec::Parameter],
+) -> String
+{
+    let param_list = get_param_list( type_context, params );
+    match &method.builder_param {
+        Some( builder_type ) => {
+            let builder_param = match get_nonnull_annotation( type_context ) {
+                Some( a ) => format!( "@{} {} _builder", a, builder_type ),
+                None => format!( "{} _builder", builder_type ),
+            };
+            if param_list.is_empty() {
+                builder_param
+            } else {
+                format!( "{}, {}", builder_param, param_list )
+            }
+        },
+        None => param_list,
+    }
+}
+
+/// Formats the constructor parameter list.
+///
+/// The reference type parameters are annotated with the `java_nonnull` or the `java_nullable`
+/// annotation if there is one.
+fn get_param_list(
+    type_context : &TypeContext,
+    params : &[spec::Parameter],
+) -> String
+{
+    let nonnull = get_nonnull_annotation( type_context );
+    let nullable = type_context.file_context.plugin_context.get_option( "java_nullable" );
+    utils::join( params, ", ", |p| {
+        let param_type = get_type( type_context, &p.param_type );
+        let annotation = if ! is_reference_type( &p.param_type ) {
+            None
+        } else if is_nullable_type( type_context, &p.param_type ) {
+            nullable.or( nonnull )
+        } else {
+            nonnull
+        };
+        match annotation {
+            Some( a ) => format!( "@{} {} {}", a, param_type, to_camel_case( p.name ) ),
+            None => format!( "{} {}", param_type, to_camel_case( p.name ) ),
+        }
+    } )
+}
+
+/// Checks whether the parameter is annotated with the `java_nullable` annotation.
+///
+/// The annotation marks the message parameters. It doesn't affect the null checks.
+fn is_nullable_type(
+    type_context : &TypeContext,
+    param_type : &spec::ParamType
+) -> bool
+{
+    match param_type {
+        spec::ParamType::Custom( name ) => type_context.resolve_type( name )
+                .map( |t| matches!( t.type_descriptor, TypeDescriptor::Message( _ ) ) )
+                .unwrap_or( false ),
+        _ => false,
+    }
+}
+
+/// Gets the annotation given with the `java_nonnull` plugin parameter, such as
+/// `javax.annotation.Nonnull`.
+fn get_nonnull_annotation<'a>(
+    type_context : &TypeContext<'a>
+) -> Option<&'a str>
+{
+    type_context.file_context.plugin_context.get_option( "java_nonnull" )
+}
+
+/// Annotates the constructor return value as non-null if the `java_nonnull` annotation is given.
+///
+/// The constructors never return null.
+fn write_nonnull_annotation(
+    out : &mut String,
+    type_context : &TypeContext,
+) -> Result<(), GeneratorError>
+{
+    if let Some( annotation ) = get_nonnull_annotation( type_context ) {
+        writeln!( out, "@{}", annotation )?;
+    }
+    Ok(())
+}
+
+/// Checks whether the parameter type is a Java reference type that may be null.
+fn is_reference_type( param_type : &spec::ParamType ) -> bool
+{
+    matches!( param_type,
+        spec::ParamType::String
+        | spec::ParamType::Bytes
+        | spec::ParamType::Custom( _ )
+        | spec::ParamType::Repeated( _ )
+        | spec::ParamType::Map( _, _ ) )
+}
+
+/// Gets the Java method name for a constructor.
+///
+/// Constructor names such as `Default` would turn into Java keywords. These are suffixed with an
+/// underscore the same way protoc handles field names that are keywords.
+fn get_ctor_name( name : &str ) -> String
+{
+    const KEYWORDS : &[&str] = &[
+        "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class",
+        "const", "continue", "default", "do", "double", "else", "enum", "extends", "false",
+        "final", "finally", "float", "for", "goto", "if", "implements", "import", "instanceof",
+        "int", "interface", "long", "native", "new", "null", "package", "private", "protected",
+        "public", "return", "short", "static", "strictfp", "super", "switch", "synchronized",
+        "this", "throw", "throws", "transient", "true", "try", "void", "volatile", "while",
+    ];
+
+    let name = to_camel_case( name );
+    if KEYWORDS.contains( &name.as_str() ) {
+        format!( "{}_", name )
+    } else {
+        name
+    }
+}
+
+/// Gets the path of the Java file that contains the type.
+///
+/// This mirrors the output path of the original java generator. The output path uses the java
+/// package (net.foo.bar) as path (net/foo/bar) and the final file name is based on the outer class
+/// name. With `java_multiple_files` each top level type has a file of its own and the nested types
+/// are defined within the file of their top level parent.
+fn get_output_path(
+    type_context : &TypeContext
+) -> String
+{
+    let file_descriptor = type_context.file_context.descriptor;
+    let class_name = if file_descriptor.get_options().get_java_multiple_files() {
+        type_context.get_rel_name().split( '.' ).next().unwrap_or_default().to_string()
+    } else {
+        get_outer_class( file_descriptor )
+    };
+
+    let package = get_java_package( file_descriptor );
+    if package.is_empty() {
+        format!( "{}.java", class_name )
+    } else {
+        format!( "{}/{}.java", package.replace( ".", "/" ), class_name )
+    }
+}
+
 /// Gets the Java package.
 fn get_java_package(
     descriptor : &protobuf::descriptor::FileDescriptorProto
@@ -151,66 +553,515 @@ fn get_java_package(
 /// Gets the Java outer class name.
 fn get_outer_class(
     descriptor : &protobuf::descriptor::FileDescriptorProto
-) -> &str
+) -> String
 {
     // Prefer the explicit outer class name defined as an option in the .proto file
     let opts = descriptor.get_options();
     if opts.has_java_outer_classname() {
-        return opts.get_java_outer_classname()
+        return opts.get_java_outer_classname().to_string()
     }
 
-    // No outer class name defined. Use the file name.
-    let file = Path::new( descriptor.get_name() );
-    return file.file_stem().and_then( |stem| stem.to_str() ).unwrap()
+    // No outer class name defined. Use the file name the same way protoc does: `foo_bar.proto`
+    // becomes `FooBar`.
+    let file_name = Path::new( descriptor.get_name() )
+        .file_name()
+        .and_then( |name| name.to_str() )
+        .unwrap_or_default();
+    let stem = file_name.strip_suffix( ".protodevel" )
+        .or_else( || file_name.strip_suffix( ".proto" ) )
+        .unwrap_or( file_name );
+    let class_name = underscores_to_camel_case( stem );
+
+    // The outer class must not have the same name as any of the types within it.
+    if has_conflicting_class_name( descriptor, &class_name ) {
+        format!( "{}OuterClass", class_name )
+    } else {
+        class_name
+    }
+}
+
+/// Converts a file name into a class name.
+///
+/// Mirrors the protoc `UnderscoresToCamelCase`: Letters following anything other than a letter
+/// are capitalized and everything other than letters and digits is dropped.
+fn underscores_to_camel_case( name : &str ) -> String
+{
+    let mut out = String::with_capacity( name.len() );
+    let mut capitalize_next = true;
+    for c in name.chars() {
+        if c.is_ascii_lowercase() {
+            out.push( if capitalize_next { c.to_ascii_uppercase() } else { c } );
+            capitalize_next = false;
+        } else if c.is_ascii_uppercase() {
+            out.push( c );
+            capitalize_next = false;
+        } else if c.is_ascii_digit() {
+            out.push( c );
+            capitalize_next = true;
+        } else {
+            capitalize_next = true;
+        }
+    }
+    out
+}
+
+/// Checks whether any of the types in the file would have the same Java class name.
+fn has_conflicting_class_name(
+    descriptor : &protobuf::descriptor::FileDescriptorProto,
+    class_name : &str
+) -> bool
+{
+    fn message_conflicts(
+        message : &protobuf::descriptor::DescriptorProto,
+        class_name : &str
+    ) -> bool
+    {
+        message.get_name() == class_name
+            || message.get_enum_type().iter().any( |e| e.get_name() == class_name )
+            || message.get_nested_type().iter().any( |m| message_conflicts( m, class_name ) )
+    }
+
+    descriptor.get_enum_type().iter().any( |e| e.get_name() == class_name )
+        || descriptor.get_service().iter().any( |s| s.get_name() == class_name )
+        || descriptor.get_message_type().iter().any( |m| message_conflicts( m, class_name ) )
+}
+
+/// Gets the name of the method that applies the constructor to an existing builder.
+fn get_apply_name( name : &str ) -> String
+{
+    format!( "apply{}", to_pascal_case( name ) )
+}
+
+/// Gets the name of the standalone mode constructor that returns a builder.
+fn get_builder_ctor_name( name : &str ) -> String
+{
+    format!( "{}Builder", get_ctor_name( name ).trim_end_matches( '_' ) )
+}
+
+/// Gets the fully qualified Java class name of a proto type.
+///
+/// The classes are nested within the outer class unless the file uses `java_multiple_files`.
+/// Nested proto types are nested classes within their parent classes.
+fn get_java_class(
+    type_context : &TypeContext
+) -> String
+{
+    let descriptor = type_context.file_context.descriptor;
+    let outer_class = get_outer_class( descriptor );
+    let mut path = vec![ get_java_package( descriptor ) ];
+    if ! descriptor.get_options().get_java_multiple_files() {
+        path.push( &outer_class );
+    }
+    path.push( type_context.get_rel_name() );
+
+    path.into_iter()
+        .filter( |segment| ! segment.is_empty() )
+        .collect::<Vec<_>>()
+        .join( "." )
+}
+
+/// Resolves a type referenced in the spec relative to the current type.
+fn resolve_type<'a>(
+    context : &TypeContext<'a>,
+    type_name : &str
+) -> Result<TypeContext<'a>, GeneratorError>
+{
+    context.resolve_type( type_name )
+        .ok_or_else( || GeneratorError::from( format!( "Unknown type `{}`", type_name ) ) )
 }
 
 /// Convert Protobuf types into Java types.
-fn get_type( 
+///
+/// Message and enum types are resolved relative to the type the constructor belongs to.
+fn get_type(
+    type_context : &TypeContext,
     param_type : &spec::ParamType
 ) -> String
 {
+    // The unsigned protobuf types use the signed Java types of the same width as in the code
+    // protoc generates.
     match param_type {
-        spec::ParamType::Int32 => "int".to_string(),
+        spec::ParamType::Double => "double".to_string(),
+        spec::ParamType::Float => "float".to_string(),
+        spec::ParamType::Int32
+            | spec::ParamType::Uint32
+            | spec::ParamType::Sint32
+            | spec::ParamType::Fixed32
+            | spec::ParamType::Sfixed32 => "int".to_string(),
+        spec::ParamType::Int64
+            | spec::ParamType::Uint64
+            | spec::ParamType::Sint64
+            | spec::ParamType::Fixed64
+            | spec::ParamType::Sfixed64 => "long".to_string(),
+        spec::ParamType::Bool => "boolean".to_string(),
         spec::ParamType::String => "String".to_string(),
-        spec::ParamType::Custom( name ) => name.to_string(),
+        spec::ParamType::Bytes => "com.google.protobuf.ByteString".to_string(),
+        spec::ParamType::Custom( name ) => type_context.resolve_type( name )
+                .map( |t| get_java_class( &t ) )
+                .unwrap_or_else( || name.to_string() ),
+        spec::ParamType::Repeated( item ) => format!( "Iterable<{}>", get_boxed_type( type_context, item ) ),
+        spec::ParamType::Map( key, value ) => format!( "java.util.Map<{}, {}>",
+                get_boxed_type( type_context, key ),
+                get_boxed_type( type_context, value ) ),
+    }
+}
+
+/// Convert Protobuf types into Java types usable as generic type arguments.
+fn get_boxed_type(
+    type_context : &TypeContext,
+    param_type : &spec::ParamType
+) -> String
+{
+    match get_type( type_context, param_type ).as_str() {
+        "double" => "Double".to_string(),
+        "float" => "Float".to_string(),
+        "int" => "Integer".to_string(),
+        "long" => "Long".to_string(),
+        "boolean" => "Boolean".to_string(),
+        other => other.to_string(),
     }
 }
 
 /// Convert constructor value expression into Java expression.
-fn get_value( context : &TypeContext, expr : &spec::Expr ) -> String
+///
+/// The expected type of the value decides the form of the numeric literals. It is `None` when the
+/// value is passed to a stand-alone function.
+fn get_value(
+    context : &TypeContext,
+    expr : &spec::Expr,
+    expected : Option<&ValueType>,
+) -> Result<String, GeneratorError>
 {
-    match expr {
+    Ok( match &expr.kind {
 
         // Basic expressions.
-        spec::Expr::Bool( b ) => format!( "{:?}", b ),
-        spec::Expr::Integer( i ) => format!( "{}", i ),
-        spec::Expr::Float( f ) => format!( "{}", f ),
-        spec::Expr::Ref( r ) => to_camel_case( r ),
-        spec::Expr::Enum( e ) => format!( "{}.{}", e.enum_name, e.value_name ),
+        spec::ExprKind::Bool( b ) => format!( "{:?}", b ),
+        spec::ExprKind::Integer( i ) => get_integer_literal( *i, expected ),
+        spec::ExprKind::Float( f ) => get_float_literal( *f, expected ),
+        spec::ExprKind::String( raw ) => get_string_literal( &unescape( raw )? ),
+        spec::ExprKind::Bytes( b ) => get_bytes_literal( &b.to_bytes()
+                .map_err( |_| GeneratorError::from( "Invalid bytes literal" ) )? ),
+        spec::ExprKind::Ref( r ) => to_camel_case( r ),
+        spec::ExprKind::Enum( e ) => format!( "{}.{}",
+                get_java_class( &resolve_type( context, e.enum_name )? ),
+                e.value_name ),
 
         // Function calls are a bit more complex.
-        spec::Expr::Call( c ) => {
+        spec::ExprKind::Call( c ) => {
 
             // The function may be scoped (Type::Function) or stand-alone (Function).
             //
-            // For now the stand-alone functions are unimplemented. These are meant to be utility
-            // functions that would be defined by hand for each language. Alternatively we'll never
-            // use these and just define some `INTERNAL` type name, etc. that implements these
-            // functions.
-            let func = match c.type_name {
-                Some( t ) => format!( "{}.{}", t, to_camel_case(c.func_name) ),
-                None => unimplemented!(),
+            // The stand-alone functions are utility functions defined by hand in the helper class
+            // given in the plugin parameter.
+            //
+            // The argument types are known only for the constructors. These are relative to the
+            // type that defines the constructor.
+            let ( func, param_types ) = match c.type_name {
+                Some( t ) => {
+                    let target = resolve_type( context, t )?;
+                    let param_types = target.type_spec
+                        .and_then( |s| s.constructors.iter().find( |t| t.name == c.func_name ) )
+                        .map( |ctor| ctor.params.iter()
+                            .map( |p| ValueType::of_param( &target, &p.param_type ) )
+                            .collect() )
+                        .unwrap_or_else( Vec::new );
+                    let func = format!( "{}.{}",
+                        get_constructors_class( &target )?,
+                        get_ctor_name( c.func_name ) );
+                    ( func, param_types )
+                },
+                None => {
+                    let func = format!( "{}.{}",
+                        get_helper_class( &context.file_context.plugin_context )?,
+                        get_ctor_name( c.func_name ) );
+                    ( func, vec![] )
+                },
             };
 
             // Each function parameter is resolved with a recursive call to this
             // function.
             let params = c.args
                 .iter()
-                .map( |v| get_value( context, v ) )
-                .collect::<Vec<_>>()
+                .enumerate()
+                .map( |( idx, v )| get_value(
+                        context, v, param_types.get( idx ).and_then( |t| t.as_ref() ) ) )
+                .collect::<Result<Vec<_>, _>>()?
                 .join(", ");
 
             format!( "{}({})", func, params )
         }
+    } )
+}
+
+/// Formats an integer literal for the expected type.
+///
+/// Java integer literals are `int` unless suffixed with `L`. The unsigned 32-bit values above the
+/// `int` range are stored in `int` as two's complement the same way the protoc generated code does.
+fn get_integer_literal(
+    value : i64,
+    expected : Option<&ValueType>,
+) -> String
+{
+    let fits_int = value >= i64::from( i32::MIN ) && value <= i64::from( i32::MAX );
+    match expected {
+        Some( ValueType::Float ) => format!( "{}f", value ),
+        Some( ValueType::Double ) => format!( "{}d", value ),
+        Some( ValueType::Int64 ) | Some( ValueType::Uint64 ) => format!( "{}L", value ),
+        Some( ValueType::Uint32 ) if ! fits_int => format!( "(int) {}L", value ),
+        _ if ! fits_int => format!( "{}L", value ),
+        _ => format!( "{}", value ),
+    }
+}
+
+/// Formats a floating point literal for the expected type.
+///
+/// Java floating point literals are `double` unless suffixed with `f`.
+fn get_float_literal(
+    value : f64,
+    expected : Option<&ValueType>,
+) -> String
+{
+    // The debug format always includes the decimal point or the exponent so the literal isn't
+    // mistaken for an integer.
+    match expected {
+        Some( ValueType::Float ) => format!( "{:?}f", value ),
+        _ => format!( "{:?}", value ),
+    }
+}
+
+/// Gets the helper class that implements the stand-alone functions.
+fn get_helper_class<'a>(
+    context : &PluginContext<'a>
+) -> Result<&'a str, GeneratorError>
+{
+    context.get_option( "java_helpers" )
+        .ok_or_else( || GeneratorError::from(
+                "Stand-alone functions require the `java_helpers` plugin parameter" ) )
+}
+
+/// Reads the functions declared in the helper manifest.
+///
+/// Each non-empty line of the manifest declares a function by its name and the number of
+/// arguments it takes, such as `now/0`. Lines starting with `#` are comments.
+fn read_helper_manifest(
+    context : &PluginContext
+) -> Result<HashSet<( String, usize )>, GeneratorError>
+{
+    let path = context.get_option( "java_helper_manifest" )
+        .ok_or_else( || GeneratorError::from(
+                "The `java_helpers` plugin parameter requires the `java_helper_manifest` parameter" ) )?;
+    let content = std::fs::read_to_string( path )
+        .map_err( |e| format!( "{}: Could not read the helper manifest: {}", path, e ) )?;
+
+    let mut functions = HashSet::new();
+    for ( idx, line ) in content.lines().enumerate() {
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with( '#' ) {
+            continue;
+        }
+
+        let function = line.find( '/' )
+            .and_then( |slash| line[ slash + 1 .. ].parse::<usize>().ok()
+                .map( |arity| ( line[ .. slash ].trim().to_string(), arity ) ) )
+            .ok_or_else( || format!( "{}:{}: Expected `name/arity`", path, idx + 1 ) )?;
+        functions.insert( function );
+    }
+
+    Ok( functions )
+}
+
+/// Ensures all the stand-alone functions called by the constructors exist in the helper manifest.
+fn check_helper_calls(
+    context : &PluginContext
+) -> Result<(), GeneratorError>
+{
+    let mut calls = vec![];
+    for ( type_context, type_spec ) in context.iter_generated_types() {
+
+        let file = type_context.spec_file.expect( "Generated types always have a spec file" );
+        for ctor in &type_spec.constructors {
+            let values = ctor.params.iter()
+                .filter_map( |p| p.default.as_ref() )
+                .chain( ctor.initializers.iter().map( |i| &i.value ) );
+            for value in values {
+                collect_helper_calls( file, value, &mut calls );
+            }
+        }
+    }
+
+    if calls.is_empty() {
+        return Ok(());
+    }
+
+    // The manifest is needed only if there are calls to check.
+    get_helper_class( context )?;
+    let functions = read_helper_manifest( context )?;
+
+    let diagnostics = calls.into_iter()
+        .filter( |( _, call )| ! functions.contains( &( call.func_name.to_string(), call.args.len() ) ) )
+        .map( |( file, call )| Diagnostic::new(
+                file, call.span,
+                format!( "Function `{}` taking {} arguments is not declared in the helper manifest",
+                    call.func_name, call.args.len() ) ) )
+        .collect::<Vec<_>>();
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err( diagnostics.into() )
+    }
+}
+
+/// Collects the stand-alone function calls within the expression.
+fn collect_helper_calls<'a>(
+    file : &'a spec::File<'a>,
+    expr : &'a spec::Expr<'a>,
+    calls : &mut Vec<( &'a spec::File<'a>, &'a spec::Call<'a> )>
+)
+{
+    if let spec::ExprKind::Call( c ) = &expr.kind {
+        if c.type_name.is_none() {
+            calls.push( ( file, c ) );
+        }
+        for arg in &c.args {
+            collect_helper_calls( file, arg, calls );
+        }
+    }
+}
+
+/// Resolves the escape sequences of a spec string literal.
+fn unescape( raw : &str ) -> Result<String, GeneratorError>
+{
+    spec::unescape( raw )
+        .map_err( |_| GeneratorError::from( format!( "Invalid string literal \"{}\"", raw ) ) )
+}
+
+/// Formats bytes as a Java ByteString expression.
+fn get_bytes_literal( value : &[u8] ) -> String
+{
+    // Java bytes are signed so the values above 0x7f need an explicit cast.
+    format!( "com.google.protobuf.ByteString.copyFrom(new byte[] {{{}}})",
+        utils::join( value, ", ", |b| format!( "(byte) 0x{:02x}", b ) ) )
+}
+
+/// Formats a string as a Java string literal.
+fn get_string_literal( value : &str ) -> String
+{
+    let mut out = String::from( "\"" );
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str( "\\\"" ),
+            '\\' => out.push_str( "\\\\" ),
+            '\n' => out.push_str( "\\n" ),
+            '\r' => out.push_str( "\\r" ),
+            '\t' => out.push_str( "\\t" ),
+
+            // Java translates the `\u` escapes before parsing the source so the line breaks
+            // would end up breaking the literal. Octal escapes are safe for the control
+            // characters.
+            c if c.is_ascii_control() => out.push_str( &format!( "\\{:03o}", c as u32 ) ),
+
+            // Avoid depending on the source file encoding with everything else.
+            c if ! c.is_ascii() => {
+                let mut utf16 = [ 0; 2 ];
+                for unit in c.encode_utf16( &mut utf16 ) {
+                    out.push_str( &format!( "\\u{:04x}", unit ) );
+                }
+            },
+            c => out.push( c ),
+        }
+    }
+    out.push( '"' );
+    out
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use protobuf::descriptor::{
+        DescriptorProto, EnumDescriptorProto, FileDescriptorProto, ServiceDescriptorProto };
+
+    fn file( name : &str ) -> FileDescriptorProto
+    {
+        let mut file = FileDescriptorProto::new();
+        file.set_name( name.to_string() );
+        file
+    }
+
+    fn message( name : &str ) -> DescriptorProto
+    {
+        let mut message = DescriptorProto::new();
+        message.set_name( name.to_string() );
+        message
+    }
+
+    fn enumeration( name : &str ) -> EnumDescriptorProto
+    {
+        let mut e = EnumDescriptorProto::new();
+        e.set_name( name.to_string() );
+        e
+    }
+
+    #[test]
+    fn outer_class_from_file_name()
+    {
+        assert_eq!( get_outer_class( &file( "foo.proto" ) ), "Foo" );
+        assert_eq!( get_outer_class( &file( "foo_bar.proto" ) ), "FooBar" );
+        assert_eq!( get_outer_class( &file( "dir/foo_bar.proto" ) ), "FooBar" );
+        assert_eq!( get_outer_class( &file( "foo-bar.proto" ) ), "FooBar" );
+        assert_eq!( get_outer_class( &file( "foo.bar.proto" ) ), "FooBar" );
+        assert_eq!( get_outer_class( &file( "fooBar.proto" ) ), "FooBar" );
+        assert_eq!( get_outer_class( &file( "naming_rules_2x.proto" ) ), "NamingRules2X" );
+        assert_eq!( get_outer_class( &file( "v2_api.proto" ) ), "V2Api" );
+        assert_eq!( get_outer_class( &file( "legacy.protodevel" ) ), "Legacy" );
+    }
+
+    #[test]
+    fn outer_class_from_option()
+    {
+        let mut descriptor = file( "foo_bar.proto" );
+        descriptor.mut_options().set_java_outer_classname( "Explicit".to_string() );
+        descriptor.mut_message_type().push( message( "Explicit" ) );
+
+        // The explicit name is used as is even if it conflicts with a type.
+        assert_eq!( get_outer_class( &descriptor ), "Explicit" );
+    }
+
+    #[test]
+    fn outer_class_suffix_on_conflicts()
+    {
+        let mut descriptor = file( "foo_bar.proto" );
+        descriptor.mut_message_type().push( message( "FooBar" ) );
+        assert_eq!( get_outer_class( &descriptor ), "FooBarOuterClass" );
+
+        let mut descriptor = file( "foo_bar.proto" );
+        descriptor.mut_enum_type().push( enumeration( "FooBar" ) );
+        assert_eq!( get_outer_class( &descriptor ), "FooBarOuterClass" );
+
+        let mut descriptor = file( "foo_bar.proto" );
+        let mut service = ServiceDescriptorProto::new();
+        service.set_name( "FooBar".to_string() );
+        descriptor.mut_service().push( service );
+        assert_eq!( get_outer_class( &descriptor ), "FooBarOuterClass" );
+
+        // Nested types conflict as well.
+        let mut descriptor = file( "foo_bar.proto" );
+        let mut parent = message( "Parent" );
+        parent.mut_nested_type().push( message( "FooBar" ) );
+        descriptor.mut_message_type().push( parent );
+        assert_eq!( get_outer_class( &descriptor ), "FooBarOuterClass" );
+
+        let mut descriptor = file( "foo_bar.proto" );
+        let mut parent = message( "Parent" );
+        parent.mut_enum_type().push( enumeration( "FooBar" ) );
+        descriptor.mut_message_type().push( parent );
+        assert_eq!( get_outer_class( &descriptor ), "FooBarOuterClass" );
+
+        // Names differing only in case don't conflict.
+        let mut descriptor = file( "foo_bar.proto" );
+        descriptor.mut_message_type().push( message( "Foobar" ) );
+        assert_eq!( get_outer_class( &descriptor ), "FooBar" );
     }
 }
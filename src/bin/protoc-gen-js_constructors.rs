@@ -1,8 +1,9 @@
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write;
 
 use protoc_gen_constructors::prelude::*;
-use protoc_gen_constructors::documentation::write_javadoc;
+use protoc_gen_constructors::documentation::write_jsdoc;
 
 use inflector::cases::camelcase::to_camel_case;
 use inflector::cases::pascalcase::to_pascal_case;
@@ -20,6 +21,18 @@ fn run(
     let mut response = CodeGeneratorResponse::default();
     for file_context in context.iter_generated_files() {
 
+        // The constructors are written first so we'll know which other modules they need.
+        let mut modules = Modules::new( file_context.descriptor );
+        let mut body = String::new();
+        {
+            let mut out = IndentingWriter::new( &mut body, "    " );
+            for ( type_context, spec ) in file_context.iter_generated_types() {
+                for ctor in &spec.constructors {
+                    write_ctor( &mut out, &type_context, ctor, &mut modules )?;
+                }
+            }
+        }
+
         let mut result = String::new();
         let mut out = IndentingWriter::new( &mut result, "    " );
 
@@ -28,25 +41,26 @@ fn run(
         //
         // The file we are generating is placed in the same folder so we'll just need the file name
         // here, not the full path.
-        let stem = std::path::Path::new( file_context.descriptor.get_name() )
-            .file_stem()
-            .and_then( |stem| stem.to_str() )
-            .unwrap();
-        let package = format!( "{}", stem.to_lowercase() );
-        writeln!( out, "var __ = require('./{}_pb');", package )?;
-        writeln!( out, "" )?;
+        let module_path = get_module_path( file_context.descriptor );
+        let file_name = module_path.rsplit( '/' ).next().unwrap_or( &module_path );
+        writeln!( out, "var __ = require('./{}');", file_name )?;
+        for ( path, alias ) in &modules.required {
+            writeln!( out, "var {} = require('{}');", alias, path )?;
+        }
 
-        for ( type_context, spec ) in file_context.iter_generated_types() {
-            for ctor in &spec.constructors {
-                write_ctor( &mut out, &type_context, ctor )?;
-            }
+        // The constructors of the other files are added to the types in their modules when the
+        // constructor modules are loaded.
+        for path in &modules.constructors {
+            writeln!( out, "require('{}');", path )?;
         }
+        writeln!( out, "" )?;
+        write!( out, "{}", body )?;
 
         // Individual file responses are added to the total response we'll
         // return to protoc in the end.
         let mut ctor_response = CodeGeneratorResponse_File::new();
-        let output_path = format!( "{}_pb-constructors.js",
-            package );
+        let output_path = format!( "{}-constructors.js",
+            module_path );
         ctor_response.set_name(output_path);
         ctor_response.set_content(result);
         response.mut_file().push(ctor_response);
@@ -59,15 +73,20 @@ fn run(
 fn write_ctor(
     out : &mut IndentingWriter,
     type_context : &TypeContext,
-    ctor : &spec::Constructor
+    ctor : &spec::Constructor,
+    modules : &mut Modules,
 ) -> Result<(), GeneratorError>
 {
     // Since JS is a dynamic language, we'll just slap the constructors
     // on each type by hand.
 
-    write_javadoc( out, ctor )?;
+    // Parameters with default values are optional in JSDoc.
+    write_jsdoc( out, ctor, &|p| match p.default {
+        Some( _ ) => format!( "{}=", get_type( type_context, &p.param_type ) ),
+        None => get_type( type_context, &p.param_type ),
+    } )?;
 
-    let class_name = to_pascal_case( type_context.get_name() );
+    let class_name = get_class_name( type_context );
     let ctor_name = to_camel_case( ctor.name );
     let param_list = utils::join( &ctor.params, ", ", |p| format!( "{}",
                    to_camel_case( &p.name ) ) );
@@ -86,17 +105,44 @@ fn write_ctor(
     writeln!( out, "__.{}.{} = function {}({}) {{",
         class_name,
         ctor_name,
-        ctor_name,
+        get_function_name( &ctor_name ),
         param_list )?;
     out.indent();
 
+    // Omitted arguments are undefined so replace those with the default values.
+    for param in &ctor.params {
+        if let Some( default ) = &param.default {
+            let param_name = to_camel_case( param.name );
+            writeln!( out, "if ({} === undefined) {{ {} = {}; }}",
+                param_name,
+                param_name,
+                get_value( type_context, default, modules )? )?;
+        }
+    }
+
     writeln!( out, "var _self = new __.{}();", class_name )?;
 
     for initializer in &ctor.initializers {
-        writeln!( out, "_self.set{}({});",
-            to_pascal_case( initializer.field ),
-            get_value( type_context, &initializer.value ),
-        )?;
+
+        let field_name = to_pascal_case( initializer.field );
+        let value = get_value( type_context, &initializer.value, modules )?;
+
+        if type_context.get_map_entry( initializer.field ).is_some() {
+
+            // Map fields have no setters. Instead the entries are copied into the map owned by
+            // the message.
+            writeln!( out, "{}.forEach(function(value, key) {{ _self.get{}Map().set(key, value); }});",
+                value,
+                field_name )?;
+
+        } else if type_context.is_repeated_field( initializer.field ) {
+
+            // Repeated fields are set as arrays through the `List` setter.
+            writeln!( out, "_self.set{}List({});", field_name, value )?;
+
+        } else {
+            writeln!( out, "_self.set{}({});", field_name, value )?;
+        }
     }
 
     writeln!( out, "return _self;" )?;
@@ -107,30 +153,322 @@ fn write_ctor(
     Ok(())
 }
 
+/// Tracks the protoc generated modules the constructors refer to.
+struct Modules<'a>
+{
+    /// Proto file the constructors are generated for. Its types are available through `__`.
+    file_name : &'a str,
+
+    /// Paths and aliases of the other modules required by the constructors.
+    required : BTreeMap<String, String>,
+
+    /// Paths of the constructor modules of the other files the constructors call.
+    constructors : BTreeSet<String>,
+}
+
+impl<'a> Modules<'a>
+{
+    fn new( descriptor : &'a protobuf::descriptor::FileDescriptorProto ) -> Modules<'a>
+    {
+        Modules {
+            file_name: descriptor.get_name(),
+            required: BTreeMap::new(),
+            constructors: BTreeSet::new(),
+        }
+    }
+
+    /// Gets the alias of the module that defines the type, requiring it if needed.
+    fn get_alias( &mut self, type_context : &TypeContext ) -> String
+    {
+        let descriptor = type_context.file_context.descriptor;
+        if descriptor.get_name() == self.file_name {
+            return "__".to_string();
+        }
+
+        // The well known types are part of the google-protobuf package. Everything else is
+        // expected to be generated in the same output folder as the current file.
+        let module_path = get_module_path( descriptor );
+        let path = if descriptor.get_name().starts_with( "google/protobuf/" ) {
+            format!( "google-protobuf/{}", module_path )
+        } else {
+            get_relative_path( self.file_name, &module_path )
+        };
+
+        if let Some( alias ) = self.required.get( &path ) {
+            return alias.clone();
+        }
+
+        // The aliases are derived from the full path. Different paths may still end up with the
+        // same alias so number the later ones.
+        let base_alias = format!( "__{}",
+            module_path.replace( |c : char| ! c.is_ascii_alphanumeric(), "_" ) );
+        let mut alias = base_alias.clone();
+        let mut counter = 1;
+        while self.required.values().any( |a| *a == alias ) {
+            counter += 1;
+            alias = format!( "{}{}", base_alias, counter );
+        }
+
+        self.required.insert( path, alias.clone() );
+        alias
+    }
+
+    /// Gets the alias of the module that defines the type for calling its constructors.
+    ///
+    /// The constructors are defined in a separate module that needs to be loaded before the
+    /// constructors exist on the type.
+    fn get_constructor_alias( &mut self, type_context : &TypeContext ) -> String
+    {
+        let descriptor = type_context.file_context.descriptor;
+        if descriptor.get_name() != self.file_name {
+            let module_path = format!( "{}-constructors", get_module_path( descriptor ) );
+            self.constructors.insert( get_relative_path( self.file_name, &module_path ) );
+        }
+        self.get_alias( type_context )
+    }
+}
+
+/// Gets the path of the protoc generated module of the proto file without the `.js` extension.
+///
+/// The path is relative to the output folder. Only the file name is lowercased.
+fn get_module_path( descriptor : &protobuf::descriptor::FileDescriptorProto ) -> String
+{
+    let name = descriptor.get_name();
+    let ( dir, file_name ) = match name.rfind( '/' ) {
+        Some( idx ) => ( &name[ .. idx + 1 ], &name[ idx + 1 .. ] ),
+        None => ( "", name ),
+    };
+    let stem = std::path::Path::new( file_name )
+        .file_stem()
+        .and_then( |stem| stem.to_str() )
+        .unwrap()
+        .to_lowercase();
+    format!( "{}{}_pb", dir, stem )
+}
+
+/// Gets the path of a module relative to the module generated for the proto file.
+///
+/// Both paths are relative to the output folder.
+fn get_relative_path( from_file : &str, to_module : &str ) -> String
+{
+    let from_dirs = from_file.split( '/' ).collect::<Vec<_>>();
+    let from_dirs = &from_dirs[ .. from_dirs.len() - 1 ];
+    let to_parts = to_module.split( '/' ).collect::<Vec<_>>();
+
+    let common = from_dirs.iter()
+        .zip( &to_parts[ .. to_parts.len() - 1 ] )
+        .take_while( |( a, b )| a == b )
+        .count();
+
+    let mut path = if common == from_dirs.len() {
+        ".".to_string()
+    } else {
+        vec![ ".." ; from_dirs.len() - common ].join( "/" )
+    };
+    for part in &to_parts[ common .. ] {
+        path.push( '/' );
+        path.push_str( part );
+    }
+    path
+}
+
+/// Resolves a type referenced in the spec relative to the current type.
+fn resolve_type<'a>(
+    context : &TypeContext<'a>,
+    type_name : &str
+) -> Result<TypeContext<'a>, GeneratorError>
+{
+    context.resolve_type( type_name )
+        .ok_or_else( || GeneratorError::from( format!( "Unknown type `{}`", type_name ) ) )
+}
+
+/// Gets the name of the type within the protoc generated module.
+///
+/// Nested types are properties of their parent types.
+fn get_class_name( type_context : &TypeContext ) -> String
+{
+    utils::join( type_context.get_rel_name().split( '.' ), ".", to_pascal_case )
+}
+
+/// Gets the name for a function expression.
+///
+/// Reserved words are fine as property names but not as function names so these are suffixed with
+/// an underscore.
+fn get_function_name( name : &str ) -> String
+{
+    const RESERVED_WORDS : &[&str] = &[
+        "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+        "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for",
+        "function", "if", "implements", "import", "in", "instanceof", "interface", "let", "new",
+        "null", "package", "private", "protected", "public", "return", "static", "super",
+        "switch", "this", "throw", "true", "try", "typeof", "var", "void", "while", "with",
+        "yield",
+    ];
+
+    if RESERVED_WORDS.contains( &name ) {
+        format!( "{}_", name )
+    } else {
+        name.to_string()
+    }
+}
+
+/// Gets the JSDoc type for a parameter type.
+///
+/// Message and enum types are referred to by their global names the same way the protoc
+/// generated code refers to them.
+fn get_type(
+    type_context : &TypeContext,
+    param_type : &spec::ParamType
+) -> String
+{
+    // The 64-bit integers are numbers by default in the protoc generated code. Bytes fields accept
+    // both base64 strings and byte arrays.
+    match param_type {
+        spec::ParamType::Double
+            | spec::ParamType::Float
+            | spec::ParamType::Int32
+            | spec::ParamType::Int64
+            | spec::ParamType::Uint32
+            | spec::ParamType::Uint64
+            | spec::ParamType::Sint32
+            | spec::ParamType::Sint64
+            | spec::ParamType::Fixed32
+            | spec::ParamType::Fixed64
+            | spec::ParamType::Sfixed32
+            | spec::ParamType::Sfixed64 => "number".to_string(),
+        spec::ParamType::Bool => "boolean".to_string(),
+        spec::ParamType::String => "string".to_string(),
+        spec::ParamType::Bytes => "(string|Uint8Array)".to_string(),
+        spec::ParamType::Custom( name ) => type_context.resolve_type( name )
+                .map( |t| format!( "proto.{}", t.full_name ) )
+                .unwrap_or_else( || name.to_string() ),
+        spec::ParamType::Repeated( item ) => format!( "Array<{}>", get_type( type_context, item ) ),
+        spec::ParamType::Map( key, value ) => format!( "Map<{}, {}>",
+                get_type( type_context, key ),
+                get_type( type_context, value ) ),
+    }
+}
+
 /// Turns a value expression into JS value.
-fn get_value( context : &TypeContext, expr : &spec::Expr ) -> String
+fn get_value(
+    context : &TypeContext,
+    expr : &spec::Expr,
+    modules : &mut Modules,
+) -> Result<String, GeneratorError>
 {
-    match expr {
-        spec::Expr::Bool( b ) => format!( "{:?}", b ),
-        spec::Expr::Integer( i ) => format!( "{}", i ),
-        spec::Expr::Float( f ) => format!( "{}", f ),
-        spec::Expr::Ref( r ) => to_camel_case( r ),
-        spec::Expr::Enum( e ) => format!( "{}.{}", e.enum_name, e.value_name ),
-        spec::Expr::Call( c ) => {
+    Ok( match &expr.kind {
+        spec::ExprKind::Bool( b ) => format!( "{:?}", b ),
+        spec::ExprKind::Integer( i ) => format!( "{}", i ),
+        spec::ExprKind::Float( f ) => format!( "{}", f ),
+        spec::ExprKind::String( raw ) => get_string_literal( &unescape( raw )? ),
+        spec::ExprKind::Bytes( b ) => get_bytes_literal( &b.to_bytes()
+                .map_err( |_| GeneratorError::from( "Invalid bytes literal" ) )? ),
+        spec::ExprKind::Ref( r ) => to_camel_case( r ),
+        spec::ExprKind::Enum( e ) => {
+            let target = resolve_type( context, e.enum_name )?;
+            format!( "{}.{}.{}",
+                modules.get_alias( &target ),
+                get_class_name( &target ),
+                e.value_name )
+        },
+        spec::ExprKind::Call( c ) => {
 
+            // The type name is relative to the current type so resolve it to find the nested
+            // types and the module that defines the type.
             let func = match c.type_name {
-                Some( t ) => format!( "__.{}.{}", t, to_camel_case( c.func_name ) ),
+                Some( t ) => {
+                    let target = resolve_type( context, t )?;
+                    format!( "{}.{}.{}",
+                        modules.get_constructor_alias( &target ),
+                        get_class_name( &target ),
+                        to_camel_case( c.func_name ) )
+                },
                 None => to_camel_case( c.func_name ),
             };
 
             // The call parameters are resolved recursively.
             let params = c.args
                 .iter()
-                .map( |v| get_value( context, v ) )
-                .collect::<Vec<_>>()
+                .map( |v| get_value( context, v, modules ) )
+                .collect::<Result<Vec<_>, _>>()?
                 .join(", ");
 
             format!( "{}({})", func, params )
         }
+    } )
+}
+
+/// Resolves the escape sequences of a spec string literal.
+fn unescape( raw : &str ) -> Result<String, GeneratorError>
+{
+    spec::unescape( raw )
+        .map_err( |_| GeneratorError::from( format!( "Invalid string literal \"{}\"", raw ) ) )
+}
+
+/// Formats bytes as a JS Uint8Array expression.
+fn get_bytes_literal( value : &[u8] ) -> String
+{
+    format!( "new Uint8Array([{}])",
+        utils::join( value, ", ", |b| format!( "0x{:02x}", b ) ) )
+}
+
+/// Formats a string as a JS string literal.
+fn get_string_literal( value : &str ) -> String
+{
+    let mut out = String::from( "\"" );
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str( "\\\"" ),
+            '\\' => out.push_str( "\\\\" ),
+            '\n' => out.push_str( "\\n" ),
+            '\r' => out.push_str( "\\r" ),
+            '\t' => out.push_str( "\\t" ),
+            c if c.is_ascii_control() => out.push_str( &format!( "\\x{:02x}", c as u32 ) ),
+
+            // Avoid depending on the source file encoding with everything else. This also takes
+            // care of the line and paragraph separators that are not allowed in older engines.
+            c if ! c.is_ascii() => {
+                let mut utf16 = [ 0; 2 ];
+                for unit in c.encode_utf16( &mut utf16 ) {
+                    out.push_str( &format!( "\\u{:04x}", unit ) );
+                }
+            },
+            c => out.push( c ),
+        }
+    }
+    out.push( '"' );
+    out
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use protobuf::descriptor::FileDescriptorProto;
+
+    fn file( name : &str ) -> FileDescriptorProto
+    {
+        let mut file = FileDescriptorProto::new();
+        file.set_name( name.to_string() );
+        file
+    }
+
+    #[test]
+    fn module_path_keeps_directories()
+    {
+        assert_eq!( get_module_path( &file( "test1.proto" ) ), "test1_pb" );
+        assert_eq!( get_module_path( &file( "a/common.proto" ) ), "a/common_pb" );
+        assert_eq!( get_module_path( &file( "b/Common.proto" ) ), "b/common_pb" );
+    }
+
+    #[test]
+    fn relative_paths_between_modules()
+    {
+        assert_eq!( get_relative_path( "x.proto", "y_pb" ), "./y_pb" );
+        assert_eq!( get_relative_path( "x.proto", "a/y_pb" ), "./a/y_pb" );
+        assert_eq!( get_relative_path( "a/x.proto", "a/y_pb" ), "./y_pb" );
+        assert_eq!( get_relative_path( "a/x.proto", "b/common_pb" ), "../b/common_pb" );
+        assert_eq!( get_relative_path( "a/b/x.proto", "a/c/y_pb" ), "../c/y_pb" );
+        assert_eq!( get_relative_path( "a/b/x.proto", "y_pb" ), "../../y_pb" );
     }
 }
@@ -1,7 +1,9 @@
 
+use std::collections::HashMap;
 use crate::protos;
 use crate::spec;
-use crate::internal_utils::DescriptorProtoExt;
+use crate::symbols::{self, SymbolIndex};
+use protobuf::descriptor::FieldDescriptorProto_Label;
 
 /// Holds context information for the whole plugin invocation.
 #[derive(Clone, Copy)]
@@ -12,6 +14,12 @@ pub struct PluginContext<'a>
 
     /// Parsed specification files.
     pub files : &'a Vec<spec::File<'a>>,
+
+    /// Index of the proto types and their specifications.
+    pub index : &'a SymbolIndex<'a>,
+
+    /// Backend options given as `key=value` items in the plugin parameter.
+    pub options : &'a HashMap<&'a str, &'a str>,
 }
 
 /// Holds context information for a single proto file.
@@ -38,6 +46,9 @@ pub struct TypeContext<'a>
     /// Type constructor specification.
     pub type_spec : Option< &'a spec::Type<'a> >,
 
+    /// Specification file that contains the type specification.
+    pub spec_file : Option< &'a spec::File<'a> >,
+
     /// Parent file context.
     pub file_context : FileContext<'a>,
 }
@@ -90,120 +101,89 @@ impl<'a> PluginContext<'a>
             .collect::<Vec<_>>()
     }
 
+    /// Gets a backend option by its name.
+    pub fn get_option(
+        &self,
+        name : &str
+    ) -> Option<&'a str>
+    {
+        self.options.get( name ).cloned()
+    }
+
     /// Gets a type by its absolute name.
     pub fn get_type(
         &self,
         type_name : &str,
     ) -> Option< TypeContext<'a> >
     {
-        if let Some( descriptors ) = self.get_type_descriptor( type_name ) {
-
-            let ( file_context, descriptor ) = descriptors;
-            let spec = self.get_type_spec( type_name );
-
-            return Some( TypeContext {
-                full_name : type_name.to_string(),
-                type_spec: spec,
-                type_descriptor : descriptor,
-                file_context,
-            } )
-        }
-
-        None
+        let indexed = self.index.get_type( type_name )?;
+        Some( TypeContext {
+            full_name : type_name.to_string(),
+            type_spec: indexed.spec.map( |( _, t )| t ),
+            spec_file: indexed.spec.map( |( f, _ )| f ),
+            type_descriptor : indexed.descriptor,
+            file_context: FileContext {
+                descriptor: indexed.file,
+                plugin_context: *self,
+            },
+        } )
     }
 
     /// Gets a type by its relative name from an absolute source.
     ///
-    /// Used to resolve type references in messages for example.
-    /// These references may be relative to any of the parent packages.
+    /// Used to resolve type references in messages for example. The references are resolved the
+    /// same way protoc resolves them:
+    ///
+    /// - Names starting with a dot are absolute.
+    /// - The scopes are searched starting from the innermost one, the source type itself, towards
+    ///   the outermost one.
+    /// - For qualified names only the first component is searched for in the scopes. Once it is
+    ///   found, the rest of the name must resolve within it. If the first component is found but
+    ///   names something other than a package or a type, the search continues in the outer scopes.
     pub fn get_rel_type(
         &self,
         source_type : &str,
         type_name : &str,
     ) -> Option< TypeContext<'a> >
     {
-        let mut source_type = source_type.split(".").collect::<Vec<_>>();
-
-        loop {
-
-            let candidate = format!(
-                "{}.{}",
-                source_type.join("."),
-                type_name );
-
-            if let Some( t ) = self.get_type( &candidate ) {
-                return Some( t.clone() );
-            }
-
-            if let Some( _ ) = source_type.pop() {
-                continue;
-            }
-
-            return None;
+        // Names with a leading dot are absolute.
+        if let Some( absolute ) = type_name.strip_prefix( '.' ) {
+            return self.get_type( absolute );
         }
-    }
 
-    /// Gets a type spec by its full name if one exists.
-    fn get_type_spec(
-        &self,
-        type_name : &str
-    ) -> Option<&'a spec::Type<'a>>
-    {
-        for file in self.files {
+        let first_part = type_name.split( '.' ).next().unwrap_or( type_name );
+        let is_compound = first_part.len() < type_name.len();
 
-            // Ensure the file contains types belonging to this package.
-            let package_start = format!( "{}.", file.package );
-            if ! type_name.starts_with( &package_start ) {
-                continue
-            }
+        let mut scope = Some( source_type ).filter( |s| ! s.is_empty() );
+        while let Some( current ) = scope {
 
-            // Resolve the relative name of the type if it exists within this file.
-            let rel_name = &type_name[ package_start.len() .. ]
-                .split(".")
-                .collect::<Vec<_>>();
+            let candidate = format!( "{}.{}", current, first_part );
+            if is_compound {
 
-            // Currently nested types are not supported.
-            for t in &file.types {
-                if let Some( found ) = t.get_nested_or_self( rel_name ) {
-                    return Some( found );
+                // Packages, messages and enums may all contain further names so the rest of the
+                // name must be found within the first component.
+                if self.index.is_package( &candidate ) || self.index.get_type( &candidate ).is_some() {
+                    return self.get_type( &format!( "{}.{}", current, type_name ) );
                 }
+
+            } else if let Some( t ) = self.get_type( &candidate ) {
+                return Some( t );
             }
+
+            scope = current.rfind( '.' ).map( |idx| &current[ .. idx ] );
         }
 
-        None
+        // Finally try the name in the global scope.
+        self.get_type( type_name )
     }
 
-    /// Gets a type descriptor by its full name if one exists.
-    fn get_type_descriptor(
+    /// Gets a type spec and the file containing it by its full name if one exists.
+    fn get_type_spec(
         &self,
         type_name : &str
-    ) -> Option<( FileContext<'a>, TypeDescriptor<'a> )>
+    ) -> Option<( &'a spec::File<'a>, &'a spec::Type<'a> )>
     {
-        for file in self.request.get_proto_file() {
-
-            // Ensure the file contains types belonging to this package.
-            let package_start = format!( "{}.", file.get_package() );
-            if ! type_name.starts_with( &package_start ) {
-                continue
-            }
-
-            // Resolve the relative name of the type if it exists within this file.
-            let rel_name = &type_name[ package_start.len() .. ]
-                .split(".")
-                .collect::<Vec<_>>();
-
-            // Currently nested types are not supported.
-            for t in file.get_message_type() {
-                if let Some( found ) = t.get_nested_or_self( rel_name ) {
-                    return Some( ( FileContext {
-                        descriptor : file,
-                        plugin_context: *self,
-                    }, found ) );
-                }
-            }
-        }
-
-        None
+        self.index.get_type( type_name )?.spec
     }
 
     /// Finds a file descriptor given a name.
@@ -212,12 +192,7 @@ impl<'a> PluginContext<'a>
         file_name : &str
     ) -> Option< &'a protobuf::descriptor::FileDescriptorProto >
     {
-        self.request
-            .get_proto_file()
-            .into_iter()
-            .filter( |f| f.get_name() == file_name )
-            .nth(0)
-            .clone()
+        self.index.get_file( file_name )
     }
 }
 
@@ -231,23 +206,39 @@ impl<'a> FileContext<'a>
         &self
     ) -> impl IntoIterator<Item = ( TypeContext<'a>, &'a spec::Type<'a> )>
     {
-        self.descriptor.get_message_type()
-            .iter()
-            .filter_map( |t| {
-                let full_name = format!( "{}.{}",
-                    self.descriptor.get_package(),
-                    t.get_name() );
-                match self.plugin_context.get_type_spec( &full_name ) {
-                    None => None,
-                    Some( type_spec ) => Some( ( TypeContext {
-                        full_name: full_name,
-                        type_descriptor: TypeDescriptor::Message( t ),
-                        type_spec: Some( type_spec ),
-                        file_context: *self,
-                    }, type_spec ) )
-                }
-            } )
-            .collect::<Vec<_>>()
+        let mut types = vec![];
+        self.collect_generated_types(
+                self.descriptor.get_package(),
+                self.descriptor.get_message_type(),
+                &mut types );
+        types
+    }
+
+    /// Collects the types with specs from the messages and their nested messages.
+    ///
+    /// The parents are collected before their nested types.
+    fn collect_generated_types(
+        &self,
+        parent_name : &str,
+        messages : &'a [protobuf::descriptor::DescriptorProto],
+        types : &mut Vec<( TypeContext<'a>, &'a spec::Type<'a> )>
+    )
+    {
+        for t in messages {
+
+            let full_name = symbols::join_name( parent_name, t.get_name() );
+            if let Some( ( spec_file, type_spec ) ) = self.plugin_context.get_type_spec( &full_name ) {
+                types.push( ( TypeContext {
+                    full_name: full_name.clone(),
+                    type_descriptor: TypeDescriptor::Message( t ),
+                    type_spec: Some( type_spec ),
+                    spec_file: Some( spec_file ),
+                    file_context: *self,
+                }, type_spec ) );
+            }
+
+            self.collect_generated_types( &full_name, t.get_nested_type(), types );
+        }
     }
 
     /// Iterates through all constructors that should be generated based on the types within this
@@ -280,5 +271,199 @@ impl<'a> TypeContext<'a>
             TypeDescriptor::Enum( e ) => e.get_name(),
         }
     }
+
+    /// Gets the name of the type relative to its package.
+    ///
+    /// For nested types this includes the names of the parent messages, such as `Outer.Inner`.
+    pub fn get_rel_name( &self ) -> &str
+    {
+        let package = self.file_context.descriptor.get_package();
+        if package.is_empty() {
+            return &self.full_name;
+        }
+
+        self.full_name
+            .get( package.len() + 1 .. )
+            .unwrap_or( &self.full_name )
+    }
+
+    /// Resolves a type name relative to this type.
+    ///
+    /// The name may refer to the nested types of this type or the types in any of the parent
+    /// scopes.
+    pub fn resolve_type(
+        &self,
+        type_name : &str
+    ) -> Option< TypeContext<'a> >
+    {
+        self.file_context.plugin_context.get_rel_type( &self.full_name, type_name )
+    }
+
+    /// Gets a message field by its name.
+    ///
+    /// Enums have no fields so this always returns `None` for them.
+    pub fn get_field(
+        &self,
+        field_name : &str
+    ) -> Option< &'a protobuf::descriptor::FieldDescriptorProto >
+    {
+        match self.type_descriptor {
+            TypeDescriptor::Message( m ) => m.get_field()
+                .iter()
+                .find( |f| f.get_name() == field_name ),
+            TypeDescriptor::Enum( _ ) => None,
+        }
+    }
+
+    /// Gets the synthesized map entry type of a map field.
+    ///
+    /// Returns `None` if the field is not a map field.
+    pub fn get_map_entry(
+        &self,
+        field_name : &str
+    ) -> Option< &'a protobuf::descriptor::DescriptorProto >
+    {
+        let field = self.get_field( field_name )?;
+        if field.get_label() != FieldDescriptorProto_Label::LABEL_REPEATED {
+            return None;
+        }
+
+        // The type names in the descriptors are absolute and start with a dot.
+        let entry_name = field.get_type_name().trim_start_matches( '.' );
+        match self.file_context.plugin_context.get_type( entry_name )?.type_descriptor {
+            TypeDescriptor::Message( m ) if m.get_options().get_map_entry() => Some( m ),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the message field is a repeated field.
+    pub fn is_repeated_field(
+        &self,
+        field_name : &str
+    ) -> bool
+    {
+        self.get_field( field_name )
+            .map( |f| f.get_label() == FieldDescriptorProto_Label::LABEL_REPEATED )
+            .unwrap_or( false )
+    }
 }
 
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use protobuf::descriptor::{DescriptorProto, FileDescriptorProto};
+
+    fn message( name : &str, nested : Vec<DescriptorProto> ) -> DescriptorProto
+    {
+        let mut message = DescriptorProto::new();
+        message.set_name( name.to_string() );
+        message.set_nested_type( nested.into() );
+        message
+    }
+
+    fn file( name : &str, package : &str, messages : Vec<DescriptorProto> ) -> FileDescriptorProto
+    {
+        let mut file = FileDescriptorProto::new();
+        file.set_name( name.to_string() );
+        file.set_package( package.to_string() );
+        file.set_message_type( messages.into() );
+        file
+    }
+
+    /// Resolves the name from the source type within a request consisting of the given files.
+    fn resolve(
+        files : Vec<FileDescriptorProto>,
+        source_type : &str,
+        type_name : &str,
+    ) -> Option<String>
+    {
+        let mut request = protos::plugin::CodeGeneratorRequest::new();
+        request.set_proto_file( files.into() );
+
+        let spec_files = vec![];
+        let options = HashMap::new();
+        let index = SymbolIndex::new( &request, &spec_files );
+        let context = PluginContext {
+            request: &request,
+            files: &spec_files,
+            index: &index,
+            options: &options,
+        };
+        context.get_rel_type( source_type, type_name ).map( |t| t.full_name )
+    }
+
+    #[test]
+    fn resolves_packages_with_shared_prefix()
+    {
+        let files = || vec![
+            file( "b.proto", "a.b", vec![ message( "Target", vec![] ) ] ),
+            file( "bc.proto", "a.bc", vec![ message( "Source", vec![] ), message( "Other", vec![] ) ] ),
+        ];
+
+        assert_eq!( resolve( files(), "a.bc.Source", "b.Target" ), Some( "a.b.Target".to_string() ) );
+        assert_eq!( resolve( files(), "a.bc.Source", "Other" ), Some( "a.bc.Other".to_string() ) );
+        assert_eq!( resolve( files(), "a.b.Target", "Other" ), None );
+    }
+
+    #[test]
+    fn does_not_treat_package_prefixes_as_packages()
+    {
+        let files = vec![
+            file( "bc.proto", "a.bc", vec![ message( "Source", vec![] ) ] ),
+            file( "global.proto", "", vec![ message( "b", vec![ message( "Source", vec![] ) ] ) ] ),
+        ];
+
+        assert_eq!( resolve( files, "a.bc.Source", "b.Source" ), Some( "b.Source".to_string() ) );
+    }
+
+    #[test]
+    fn prefers_nested_types_over_package_types()
+    {
+        let files = || vec![
+            file( "outer.proto", "pkg", vec![
+                message( "Id", vec![] ),
+                message( "Source", vec![ message( "Id", vec![] ) ] ),
+            ] ),
+            file( "other.proto", "pkg", vec![ message( "Sibling", vec![] ) ] ),
+        ];
+
+        assert_eq!( resolve( files(), "pkg.Source", "Id" ), Some( "pkg.Source.Id".to_string() ) );
+        assert_eq!( resolve( files(), "pkg.Sibling", "Id" ), Some( "pkg.Id".to_string() ) );
+        assert_eq!( resolve( files(), "pkg.Sibling", "Source.Id" ), Some( "pkg.Source.Id".to_string() ) );
+    }
+
+    #[test]
+    fn does_not_fall_back_after_binding_the_first_component()
+    {
+        // `Inner.Target` binds `Inner` to `outer.Source.Inner` which has no `Target`. protoc
+        // doesn't continue to the outer `outer.Inner.Target` in that case.
+        let files = || vec![
+            file( "types.proto", "outer", vec![
+                message( "Inner", vec![ message( "Target", vec![] ) ] ),
+            ] ),
+            file( "source.proto", "outer", vec![
+                message( "Source", vec![ message( "Inner", vec![] ) ] ),
+            ] ),
+        ];
+
+        assert_eq!( resolve( files(), "outer.Source", "Inner.Target" ), None );
+        assert_eq!( resolve( files(), "outer.Inner", "Inner.Target" ), Some( "outer.Inner.Target".to_string() ) );
+    }
+
+    #[test]
+    fn resolves_absolute_names()
+    {
+        let files = || vec![
+            file( "a.proto", "a", vec![ message( "Target", vec![] ) ] ),
+            file( "b.proto", "b", vec![
+                message( "Source", vec![] ),
+                message( "a", vec![ message( "Target", vec![] ) ] ),
+            ] ),
+        ];
+
+        assert_eq!( resolve( files(), "b.Source", "a.Target" ), Some( "b.a.Target".to_string() ) );
+        assert_eq!( resolve( files(), "b.Source", ".a.Target" ), Some( "a.Target".to_string() ) );
+        assert_eq!( resolve( files(), "b.Source", ".Target" ), None );
+    }
+}
@@ -0,0 +1,218 @@
+
+use crate::prelude::*;
+use crate::spec::Span;
+
+/// Error pointing to a location within a spec file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic
+{
+    /// Description of the problem.
+    pub message : String,
+
+    /// Path of the spec file.
+    pub path : String,
+
+    /// One-based line number.
+    pub line : usize,
+
+    /// One-based column number.
+    pub column : usize,
+
+    /// The source line the span starts on.
+    source_line : String,
+
+    /// Number of characters to underline on the source line.
+    length : usize,
+}
+
+impl Diagnostic
+{
+    /// Creates a diagnostic for a node within a parsed spec file.
+    pub fn new(
+        file : &spec::File,
+        span : Span,
+        message : impl Into<String>
+    ) -> Diagnostic
+    {
+        Diagnostic::from_source( file.path, file.source, span, message )
+    }
+
+    /// Creates a diagnostic for a span within a spec source.
+    pub fn from_source(
+        path : &str,
+        source : &str,
+        span : Span,
+        message : impl Into<String>
+    ) -> Diagnostic
+    {
+        // Find the line the span starts on.
+        let start = span.start.min( source.len() );
+        let line_start = source[ .. start ].rfind( '\n' ).map( |i| i + 1 ).unwrap_or( 0 );
+        let line_end = source[ start .. ].find( '\n' ).map( |i| start + i ).unwrap_or( source.len() );
+        let source_line = source[ line_start .. line_end ].trim_end_matches( '\r' );
+
+        // Spans covering multiple lines are only underlined until the end of the first line.
+        let end = span.end.min( line_start + source_line.len() ).max( start );
+
+        Diagnostic {
+            message: message.into(),
+            path: path.to_string(),
+            line: source[ .. line_start ].matches( '\n' ).count() + 1,
+            column: source[ line_start .. start ].chars().count() + 1,
+            source_line: source_line.to_string(),
+            length: source[ start .. end ].chars().count().max( 1 ),
+        }
+    }
+
+    /// Creates a diagnostic from a spec parser error.
+    pub fn from_parse_error(
+        path : &str,
+        source : &str,
+        error : &spec::ParseError
+    ) -> Diagnostic
+    {
+        // The expected set comes from a hash set. Sort it to keep the message stable.
+        let mut expected = error.expected.iter()
+            .map( |token| format!( "`{}`", token.escape_default() ) )
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let expected = match expected.len() {
+            0 => "end of file".to_string(),
+            1 => expected.remove( 0 ),
+            _ => format!( "one of {}", expected.join( ", " ) ),
+        };
+
+        let span = Span { start: error.offset, end: error.offset };
+        Diagnostic::from_source( path, source, span, format!( "Expected {}", expected ) )
+    }
+}
+
+impl std::fmt::Display for Diagnostic
+{
+    /// Renders the diagnostic with a caret-underlined excerpt of the source.
+    ///
+    /// ```text
+    /// test1.spec:14:9: Unknown field `type_idd`
+    ///    |
+    /// 14 |         type_idd = type
+    ///    |         ^^^^^^^^
+    /// ```
+    fn fmt( &self, f : &mut std::fmt::Formatter ) -> std::fmt::Result
+    {
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat( line_number.len() );
+
+        // Preserve the tabs on the source line so the carets line up with the excerpt.
+        let padding = self.source_line.chars()
+            .take( self.column - 1 )
+            .map( |c| if c == '\t' { '\t' } else { ' ' } )
+            .collect::<String>();
+
+        writeln!( f, "{}:{}:{}: {}", self.path, self.line, self.column, self.message )?;
+        writeln!( f, "{} |", gutter )?;
+        writeln!( f, "{} | {}", line_number, self.source_line )?;
+        write!( f, "{} | {}{}", gutter, padding, "^".repeat( self.length ) )
+    }
+}
+
+impl From<Diagnostic> for GeneratorError
+{
+    fn from( diagnostic : Diagnostic ) -> Self {
+        GeneratorError::from( diagnostic.to_string() )
+    }
+}
+
+impl From<Vec<Diagnostic>> for GeneratorError
+{
+    fn from( diagnostics : Vec<Diagnostic> ) -> Self {
+        GeneratorError::from( utils::join( &diagnostics, "\n\n", |d| d.to_string() ) )
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn from_source_position()
+    {
+        let source = "package test\n\nmessage Item\n{\n    Create() { nme = \"a\" }\n}\n";
+        let start = source.find( "nme" ).unwrap();
+        let diagnostic = Diagnostic::from_source(
+                "test.spec", source, Span { start, end: start + 3 }, "Unknown field `nme`" );
+
+        assert_eq!( diagnostic.line, 5 );
+        assert_eq!( diagnostic.column, 16 );
+        assert_eq!( diagnostic.to_string(), "\
+test.spec:5:16: Unknown field `nme`
+  |
+5 |     Create() { nme = \"a\" }
+  |                ^^^" );
+    }
+
+    #[test]
+    fn tabs_are_preserved()
+    {
+        let source = "message Item\n{\n\tCreate() {\tnme = 1 }\n}\n";
+        let start = source.find( "nme" ).unwrap();
+        let diagnostic = Diagnostic::from_source(
+                "test.spec", source, Span { start, end: start + 3 }, "Unknown field `nme`" );
+
+        assert_eq!( diagnostic.column, 13 );
+        assert_eq!( diagnostic.to_string(), "\
+test.spec:3:13: Unknown field `nme`
+  |
+3 | \tCreate() {\tnme = 1 }
+  | \t          \t^^^" );
+    }
+
+    #[test]
+    fn multi_line_span()
+    {
+        let source = "message Item\n{\n    Create()\n    {\n    }\n}\n";
+        let start = source.find( "Create" ).unwrap();
+        let end = source.rfind( '}' ).unwrap();
+        let diagnostic = Diagnostic::from_source(
+                "test.spec", source, Span { start, end }, "Invalid constructor" );
+
+        assert_eq!( diagnostic.to_string(), "\
+test.spec:3:5: Invalid constructor
+  |
+3 |     Create()
+  |     ^^^^^^^^" );
+    }
+
+    #[test]
+    fn parse_error_at_end_of_file()
+    {
+        let source = "package test\nmessage Item\n{\n    Create() { name = ";
+        let diagnostic = spec::parse( "test.spec", source ).unwrap_err();
+
+        assert_eq!( diagnostic.to_string(), "\
+test.spec:4:23: Expected one of `-`, `.`, `[0-9]`, `[a-zA-Z_]`, `\\\"`, `b\\\"`, `false`, `hex\\\"`, `true`
+  |
+4 |     Create() { name = 
+  |                       ^" );
+    }
+
+    #[test]
+    fn parse_error_expecting_end_of_file()
+    {
+        let source = "package test\n";
+        let error = spec::ParseError {
+            line: 2,
+            column: 1,
+            offset: source.len(),
+            expected: Default::default(),
+        };
+        let diagnostic = Diagnostic::from_parse_error( "test.spec", source, &error );
+
+        assert_eq!( diagnostic.to_string(), "\
+test.spec:2:1: Expected end of file
+  |
+2 | 
+  | ^" );
+    }
+}
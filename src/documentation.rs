@@ -4,13 +4,49 @@ use crate::prelude::*;
 use inflector::cases::camelcase::to_camel_case;
 
 pub fn write_javadoc(
-    out : &mut Write,
+    out : &mut dyn Write,
     ctor : &spec::Constructor
 ) -> Result<(), GeneratorError>
+{
+    write_doc_comment( out, ctor, &ctor.params, None )
+}
+
+/// Writes the Javadoc for an overload that takes only the first parameters of the constructor.
+pub fn write_overload_javadoc(
+    out : &mut dyn Write,
+    ctor : &spec::Constructor,
+    param_count : usize,
+) -> Result<(), GeneratorError>
+{
+    write_doc_comment( out, ctor, &ctor.params[ .. param_count ], None )
+}
+
+/// Writes a JSDoc comment with type annotations for the parameters.
+///
+/// Unlike the Javadoc, the JSDoc lists all the parameters even if they are not documented as
+/// their types are still useful for the tooling.
+pub fn write_jsdoc(
+    out : &mut dyn Write,
+    ctor : &spec::Constructor,
+    get_type : &dyn Fn( &spec::Parameter ) -> String,
+) -> Result<(), GeneratorError>
+{
+    write_doc_comment( out, ctor, &ctor.params, Some( get_type ) )
+}
+
+fn write_doc_comment(
+    out : &mut dyn Write,
+    ctor : &spec::Constructor,
+    params : &[spec::Parameter],
+    get_type : Option<&dyn Fn( &spec::Parameter ) -> String>,
+) -> Result<(), GeneratorError>
 {
     let has_ctor_doc = ctor.documentation.is_some();
-    let has_param_doc = ctor.params.iter().any( |p| p.documentation.is_some() );
-    if ! has_ctor_doc && ! has_param_doc {
+    let has_param_section = match get_type {
+        Some( _ ) => ! params.is_empty(),
+        None => params.iter().any( |p| p.documentation.is_some() ),
+    };
+    if ! has_ctor_doc && ! has_param_section {
         return Ok(());
     }
 
@@ -21,13 +57,20 @@ pub fn write_javadoc(
         }
     }
 
-    if has_ctor_doc && has_param_doc {
+    if has_ctor_doc && has_param_section {
         writeln!( out, " *" )?;
     }
 
-    for param in &ctor.params {
+    for param in params {
+        match get_type {
+            Some( get_type ) => writeln!( out, " * @param {{{}}} {}",
+                    get_type( param ),
+                    to_camel_case( param.name ) )?,
+            None if param.documentation.is_some() => writeln!( out, " * @param {}",
+                    to_camel_case( param.name ) )?,
+            None => continue,
+        }
         if let Some( doc ) = &param.documentation {
-            writeln!( out, " * @param {}", to_camel_case( param.name ) )?;
             for line in &doc.lines {
                 writeln!( out, " *        {}", line)?;
             }
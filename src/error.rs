@@ -2,6 +2,13 @@
 #[derive(Debug)]
 pub struct GeneratorError( String );
 
+impl std::fmt::Display for GeneratorError
+{
+    fn fmt( &self, f : &mut std::fmt::Formatter ) -> std::fmt::Result {
+        f.write_str( &self.0 )
+    }
+}
+
 impl From<std::fmt::Error> for GeneratorError
 {
     fn from( _ : std::fmt::Error ) -> Self {
@@ -16,3 +23,9 @@ impl From<&str> for GeneratorError
     }
 }
 
+impl From<String> for GeneratorError
+{
+    fn from( src : String ) -> Self {
+        GeneratorError( src )
+    }
+}
@@ -1,17 +1,23 @@
 use std::fmt::Write;
 
-mod internal_utils;
-
 pub mod protos;
 pub mod spec;
 pub mod error;
 pub mod utils;
 pub mod context;
+pub mod symbols;
 pub mod documentation;
+pub mod diagnostics;
+pub mod validation;
+pub mod type_check;
+
+#[cfg(test)]
+mod test_utils;
 
 pub mod prelude {
 
     pub use super::error::*;
+    pub use super::diagnostics::Diagnostic;
     pub use super::context::*;
 
     pub use super::protos::plugin::{CodeGeneratorResponse, CodeGeneratorResponse_File};
@@ -24,7 +30,7 @@ pub mod prelude {
 }
 
 pub struct IndentingWriter<'a> {
-    target : &'a mut Write,
+    target : &'a mut dyn Write,
     indent : &'a str,
     indent_count : usize,
     requires_indent : bool,
@@ -32,7 +38,7 @@ pub struct IndentingWriter<'a> {
 
 impl<'a> IndentingWriter<'a> {
 
-    pub fn new( target: &'a mut Write, indent: &'a str ) -> IndentingWriter<'a> {
+    pub fn new( target: &'a mut dyn Write, indent: &'a str ) -> IndentingWriter<'a> {
         IndentingWriter { target, indent, indent_count: 0, requires_indent: true }
     }
 
@@ -83,31 +89,72 @@ pub fn process<F>(f: F)
         &context::PluginContext
     ) -> Result<protos::plugin::CodeGeneratorResponse, error::GeneratorError>
 {
-
     // Deserialize the code generator request protoc is giving us.
-    let request : protos::plugin::CodeGeneratorRequest =
-        protobuf::parse_from_reader( &mut std::io::stdin() )
-            .expect( "Bad request" );
-
-    // Parse the constructors.
-    let spec_files = request.get_parameter()
-        .split(",")
-        .map( |file| std::fs::read_to_string(file).expect( "Could not read" ) )
-        .collect::<Vec<_>>();
-    let files = spec_files.iter()
-        .map(|f| spec::file(f).expect("Cold not parse"))
-        .collect::<Vec<_>>();
+    let request : Result<protos::plugin::CodeGeneratorRequest, _> =
+        protobuf::parse_from_reader( &mut std::io::stdin() );
 
-    let context = context::PluginContext {
-        request: &request,
-        files: &files,
+    let result = match request {
+        Ok( request ) => generate( &request, f ),
+        Err( e ) => Err( format!( "Bad request: {}", e ).into() ),
     };
 
-    let result = f( &context );
+    // Failures are reported back to protoc through the response error instead of panicking.
+    // This way protoc can show the user the actual reason for the failure.
+    let response = result.unwrap_or_else( |e| {
+        let mut response = protos::plugin::CodeGeneratorResponse::new();
+        response.set_error( e.to_string() );
+        response
+    } );
 
     use protobuf::Message;
-    result.unwrap()
+    response
         .write_to_writer( &mut std::io::stdout() )
         .expect( "Failed to write to stdout" );
 }
 
+/// Parses the constructor specifications and invokes the generator on them.
+fn generate<F>(
+    request : &protos::plugin::CodeGeneratorRequest,
+    f : F
+) -> Result<protos::plugin::CodeGeneratorResponse, error::GeneratorError>
+    where F:Fn(
+        &context::PluginContext
+    ) -> Result<protos::plugin::CodeGeneratorResponse, error::GeneratorError>
+{
+    // The plugin parameter lists the constructor specification files and the `key=value`
+    // options for the backends.
+    let mut spec_paths = vec![];
+    let mut options = std::collections::HashMap::new();
+    for item in request.get_parameter().split(",").filter( |item| ! item.is_empty() ) {
+        match item.find( '=' ) {
+            Some( idx ) => { options.insert( &item[ .. idx ], &item[ idx + 1 .. ] ); },
+            None => spec_paths.push( item ),
+        }
+    }
+
+    // Read and parse the constructors.
+    let mut spec_files = vec![];
+    for path in &spec_paths {
+        let content = std::fs::read_to_string( path )
+            .map_err( |e| format!( "{}: Could not read the spec file: {}", path, e ) )?;
+        spec_files.push( content );
+    }
+
+    let mut files = vec![];
+    for ( path, content ) in spec_paths.iter().zip( &spec_files ) {
+        files.push( spec::parse( path, content )? );
+    }
+
+    let index = symbols::SymbolIndex::new( request, &files );
+    let context = context::PluginContext {
+        request,
+        files: &files,
+        index: &index,
+        options: &options,
+    };
+
+    // Catch mistakes in the specs before any code is generated for them.
+    validation::validate( &context )?;
+
+    f( &context )
+}
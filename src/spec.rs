@@ -1,8 +1,20 @@
 
+use crate::diagnostics::Diagnostic;
+
+/// Byte offsets of a node within the spec source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start : usize,
+    pub end : usize,
+}
+
 #[derive(Debug)]
 pub struct File<'a> {
+    pub path : &'a str,
+    pub source : &'a str,
     pub package : &'a str,
-    pub types : Vec<Type<'a>>
+    pub types : Vec<Type<'a>>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
@@ -10,6 +22,16 @@ pub struct Type<'a> {
     pub name : String,
     pub constructors: Vec<Constructor<'a>>,
     pub nested_types: Vec<Type<'a>>,
+    pub span : Span,
+}
+
+/// Item within a message block.
+///
+/// The constructors and the nested messages may be declared in any order so the grammar parses
+/// them as members and splits them afterwards.
+enum TypeMember<'a> {
+    Constructor( Constructor<'a> ),
+    Type( Type<'a> ),
 }
 
 #[derive(Debug)]
@@ -18,11 +40,13 @@ pub struct Constructor<'a> {
     pub name : &'a str,
     pub params : Vec<Parameter<'a>>,
     pub initializers : Vec<Initializer<'a>>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
 pub struct Documentation<'a> {
     pub lines : Vec<&'a str>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
@@ -30,42 +54,236 @@ pub struct Parameter<'a> {
     pub documentation : Option<Documentation<'a>>,
     pub name : &'a str,
     pub param_type : ParamType<'a>,
+    pub default : Option<Expr<'a>>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
 pub enum ParamType<'a> {
+    Double,
+    Float,
     Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
     String,
+    Bytes,
     Custom( &'a str ),
+    Repeated( Box<ParamType<'a>> ),
+    Map( Box<ParamType<'a>>, Box<ParamType<'a>> ),
 }
 
 #[derive(Debug)]
 pub struct Initializer<'a> {
     pub field : &'a str,
     pub value : Expr<'a>,
+    pub span : Span,
+}
+
+#[derive(Debug)]
+pub struct Expr<'a> {
+    pub kind : ExprKind<'a>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
-pub enum Expr<'a> {
+pub enum ExprKind<'a> {
     Call( Call<'a> ),
     Enum( EnumValue<'a> ),
     Bool( bool ),
     Integer( i64 ),
     Float( f64 ),
+
+    /// String literal with the escape sequences as written in the source.
+    ///
+    /// Use `unescape` to get the actual string value.
+    String( &'a str ),
+    Bytes( Bytes<'a> ),
     Ref( &'a str ),
 }
 
+#[derive(Debug)]
+pub enum Bytes<'a> {
+
+    /// `b"..."` literal with the escape sequences as written in the source.
+    Escaped( &'a str ),
+
+    /// `hex"..."` literal.
+    Hex( Vec<u8> ),
+}
+
 #[derive(Debug)]
 pub struct Call<'a> {
     pub type_name : Option<&'a str>,
     pub func_name : &'a str,
-    pub args : Vec<Expr<'a>>
+    pub args : Vec<Expr<'a>>,
+    pub span : Span,
 }
 
 #[derive(Debug)]
 pub struct EnumValue<'a> {
     pub enum_name : &'a str,
     pub value_name : &'a str,
+    pub span : Span,
+}
+
+/// Invalid escape sequence within a string literal.
+#[derive(Debug)]
+pub struct EscapeError {
+
+    /// Byte offset of the escape sequence within the raw string.
+    pub offset : usize,
+
+    /// Length of the escape sequence in bytes.
+    pub len : usize,
+}
+
+/// Resolves the escape sequences in a raw string literal.
+///
+/// Supports the `\n`, `\r`, `\t`, `\0`, `\\`, `\"` and `\'` escapes as well as `\u{XXXX}` Unicode
+/// escapes.
+pub fn unescape( raw : &str ) -> Result<String, EscapeError>
+{
+    let mut out = String::with_capacity( raw.len() );
+    let mut rest = raw;
+    while let Some( idx ) = rest.find( '\\' ) {
+
+        out.push_str( &rest[ .. idx ] );
+        let offset = raw.len() - rest.len() + idx;
+        let ( c, len ) = parse_escape( &rest[ idx .. ] )
+            .map_err( |len| EscapeError { offset, len } )?;
+
+        out.push( c );
+        rest = &rest[ idx + len .. ];
+    }
+    out.push_str( rest );
+
+    Ok( out )
+}
+
+impl<'a> Bytes<'a>
+{
+    /// Gets the bytes of the literal.
+    ///
+    /// Escaped literals support the same escapes as the string literals and `\xXX` byte escapes.
+    /// Characters outside of the escapes are encoded as UTF-8.
+    pub fn to_bytes( &self ) -> Result<Vec<u8>, EscapeError>
+    {
+        let raw = match self {
+            Bytes::Hex( bytes ) => return Ok( bytes.clone() ),
+            Bytes::Escaped( raw ) => raw,
+        };
+
+        let mut out = Vec::with_capacity( raw.len() );
+        let mut rest = *raw;
+        while let Some( idx ) = rest.find( '\\' ) {
+
+            out.extend_from_slice( &rest.as_bytes()[ .. idx ] );
+            let offset = raw.len() - rest.len() + idx;
+            let sequence = &rest[ idx .. ];
+
+            let len = if sequence[ 1 .. ].starts_with( 'x' ) {
+
+                // Byte escapes are always two hex digits.
+                let byte = sequence.get( 2 .. 4 )
+                    .and_then( |hex| u8::from_str_radix( hex, 16 ).ok() )
+                    .ok_or( EscapeError { offset, len: 2 } )?;
+                out.push( byte );
+                4
+
+            } else {
+
+                let ( c, len ) = parse_escape( sequence )
+                    .map_err( |len| EscapeError { offset, len } )?;
+                let mut utf8 = [ 0; 4 ];
+                out.extend_from_slice( c.encode_utf8( &mut utf8 ).as_bytes() );
+                len
+            };
+
+            rest = &rest[ idx + len .. ];
+        }
+        out.extend_from_slice( rest.as_bytes() );
+
+        Ok( out )
+    }
+}
+
+/// Parses the escape sequence at the start of the string.
+///
+/// Returns the escaped character and the length of the sequence or the length of the invalid
+/// sequence on error.
+fn parse_escape( sequence : &str ) -> Result<( char, usize ), usize>
+{
+    let c = match sequence.chars().nth( 1 ) {
+        Some( 'n' ) => '\n',
+        Some( 'r' ) => '\r',
+        Some( 't' ) => '\t',
+        Some( '0' ) => '\0',
+        Some( '\\' ) => '\\',
+        Some( '"' ) => '"',
+        Some( '\'' ) => '\'',
+        Some( 'u' ) => {
+
+            // Unicode escapes are in the form of `\u{XXXX}`.
+            let end = match sequence.find( '}' ) {
+                Some( end ) if sequence[ 2 .. ].starts_with( '{' ) => end,
+                _ => return Err( 2 ),
+            };
+
+            let c = u32::from_str_radix( &sequence[ 3 .. end ], 16 ).ok()
+                .and_then( std::char::from_u32 )
+                .ok_or( end + 1 )?;
+            return Ok( ( c, end + 1 ) );
+        },
+        Some( other ) => return Err( 1 + other.len_utf8() ),
+        None => return Err( 1 ),
+    };
+
+    Ok( ( c, 2 ) )
+}
+
+impl<'a> ParamType<'a>
+{
+    /// Gets the parameter type by its name.
+    ///
+    /// The protobuf scalar type names map to the scalar types, everything else is a custom type.
+    pub fn from_name( name : &'a str ) -> ParamType<'a> {
+        match name {
+            "double" => ParamType::Double,
+            "float" => ParamType::Float,
+            "int32" => ParamType::Int32,
+            "int64" => ParamType::Int64,
+            "uint32" => ParamType::Uint32,
+            "uint64" => ParamType::Uint64,
+            "sint32" => ParamType::Sint32,
+            "sint64" => ParamType::Sint64,
+            "fixed32" => ParamType::Fixed32,
+            "fixed64" => ParamType::Fixed64,
+            "sfixed32" => ParamType::Sfixed32,
+            "sfixed64" => ParamType::Sfixed64,
+            "bool" => ParamType::Bool,
+            "string" => ParamType::String,
+            "bytes" => ParamType::Bytes,
+            other => ParamType::Custom( other ),
+        }
+    }
+}
+
+impl<'a> Initializer<'a>
+{
+    /// Gets the span of the field name.
+    ///
+    /// The field name is always the first token of the initializer.
+    pub fn field_span( &self ) -> Span {
+        Span { start: self.span.start, end: self.span.start + self.field.len() }
+    }
 }
 
 impl<'a> Type<'a>
@@ -90,4 +308,22 @@ impl<'a> Type<'a>
     }
 }
 
+/// Parses a constructor specification file.
+///
+/// The file path is only used for diagnostics.
+pub fn parse<'a>(
+    path : &'a str,
+    source : &'a str,
+) -> Result<File<'a>, Diagnostic>
+{
+    let mut parsed = file( source )
+        .map_err( |e| Diagnostic::from_parse_error( path, source, &e ) )?;
+
+    // The grammar has no access to the file information so fill it in here.
+    parsed.path = path;
+    parsed.source = source;
+
+    Ok( parsed )
+}
+
 include!(concat!(env!("OUT_DIR"), "/spec.rs"));
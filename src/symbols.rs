@@ -0,0 +1,165 @@
+
+use std::collections::{HashMap, HashSet};
+use protobuf::descriptor::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto};
+use crate::protos;
+use crate::spec;
+use crate::context::TypeDescriptor;
+
+/// Index of the proto files, packages and types by their fully qualified names.
+///
+/// The index is built once per plugin invocation so the lookups don't need to scan through all
+/// the files.
+pub struct SymbolIndex<'a>
+{
+    files : HashMap<&'a str, &'a FileDescriptorProto>,
+    packages : HashSet<&'a str>,
+    types : HashMap<String, IndexedType<'a>>,
+}
+
+/// Proto type within the index.
+#[derive(Clone, Copy)]
+pub struct IndexedType<'a>
+{
+    /// File that defines the type.
+    pub file : &'a FileDescriptorProto,
+
+    /// Type descriptor.
+    pub descriptor : TypeDescriptor<'a>,
+
+    /// Type constructor specification and the specification file that contains it.
+    pub spec : Option<( &'a spec::File<'a>, &'a spec::Type<'a> )>,
+}
+
+impl<'a> SymbolIndex<'a>
+{
+    /// Builds the index from the proto files in the request and the spec files.
+    ///
+    /// The specs are indexed only for the types that exist in the proto files.
+    pub fn new(
+        request : &'a protos::plugin::CodeGeneratorRequest,
+        spec_files : &'a [spec::File<'a>],
+    ) -> SymbolIndex<'a>
+    {
+        let mut index = SymbolIndex {
+            files: HashMap::new(),
+            packages: HashSet::new(),
+            types: HashMap::new(),
+        };
+
+        for file in request.get_proto_file() {
+            index.files.insert( file.get_name(), file );
+
+            // The parent packages are packages as well as far as the name resolution goes.
+            let package = file.get_package();
+            if ! package.is_empty() {
+                index.packages.insert( package );
+                for ( idx, _ ) in package.match_indices( '.' ) {
+                    index.packages.insert( &package[ .. idx ] );
+                }
+            }
+
+            index.add_enums( file, package, file.get_enum_type() );
+            index.add_messages( file, package, file.get_message_type() );
+        }
+
+        for spec_file in spec_files {
+            index.add_specs( spec_file, spec_file.package, &spec_file.types );
+        }
+
+        index
+    }
+
+    /// Gets a proto file by its name.
+    pub fn get_file(
+        &self,
+        file_name : &str
+    ) -> Option<&'a FileDescriptorProto>
+    {
+        self.files.get( file_name ).cloned()
+    }
+
+    /// Gets a type by its fully qualified name.
+    pub fn get_type(
+        &self,
+        type_name : &str
+    ) -> Option<IndexedType<'a>>
+    {
+        self.types.get( type_name ).cloned()
+    }
+
+    /// Checks whether the name is a package or a parent package of one of the proto files.
+    pub fn is_package(
+        &self,
+        name : &str
+    ) -> bool
+    {
+        self.packages.contains( name )
+    }
+
+    fn add_messages(
+        &mut self,
+        file : &'a FileDescriptorProto,
+        parent_name : &str,
+        messages : &'a [DescriptorProto],
+    )
+    {
+        for message in messages {
+            let full_name = join_name( parent_name, message.get_name() );
+            self.add_enums( file, &full_name, message.get_enum_type() );
+            self.add_messages( file, &full_name, message.get_nested_type() );
+            self.add_type( file, full_name, TypeDescriptor::Message( message ) );
+        }
+    }
+
+    fn add_enums(
+        &mut self,
+        file : &'a FileDescriptorProto,
+        parent_name : &str,
+        enums : &'a [EnumDescriptorProto],
+    )
+    {
+        for e in enums {
+            self.add_type( file, join_name( parent_name, e.get_name() ), TypeDescriptor::Enum( e ) );
+        }
+    }
+
+    fn add_type(
+        &mut self,
+        file : &'a FileDescriptorProto,
+        full_name : String,
+        descriptor : TypeDescriptor<'a>,
+    )
+    {
+        // protoc doesn't allow duplicate names but if there are any, keep the first one.
+        self.types.entry( full_name )
+            .or_insert( IndexedType { file, descriptor, spec: None } );
+    }
+
+    fn add_specs(
+        &mut self,
+        spec_file : &'a spec::File<'a>,
+        parent_name : &str,
+        types : &'a [spec::Type<'a>],
+    )
+    {
+        for t in types {
+            let full_name = join_name( parent_name, &t.name );
+            if let Some( indexed ) = self.types.get_mut( &full_name ) {
+                if indexed.spec.is_none() {
+                    indexed.spec = Some( ( spec_file, t ) );
+                }
+            }
+            self.add_specs( spec_file, &full_name, &t.nested_types );
+        }
+    }
+}
+
+/// Joins a name to its parent scope.
+pub(crate) fn join_name( parent_name : &str, name : &str ) -> String
+{
+    if parent_name.is_empty() {
+        name.to_string()
+    } else {
+        format!( "{}.{}", parent_name, name )
+    }
+}
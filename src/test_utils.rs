@@ -0,0 +1,135 @@
+
+//! Helpers for building plugin requests in the unit tests.
+
+use std::collections::HashMap;
+use protobuf::descriptor::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto };
+use protobuf::descriptor::FieldDescriptorProto_Label as Label;
+use protobuf::descriptor::FieldDescriptorProto_Type as FieldType;
+use crate::prelude::*;
+use crate::protos;
+use crate::symbols::SymbolIndex;
+use crate::validation;
+
+pub fn file( name : &str, package : &str, messages : Vec<DescriptorProto> ) -> FileDescriptorProto
+{
+    let mut file = FileDescriptorProto::new();
+    file.set_name( name.to_string() );
+    file.set_package( package.to_string() );
+    file.set_message_type( messages.into() );
+    file
+}
+
+pub fn message( name : &str, fields : Vec<FieldDescriptorProto> ) -> DescriptorProto
+{
+    let mut message = DescriptorProto::new();
+    message.set_name( name.to_string() );
+    message.set_field( fields.into() );
+    message
+}
+
+/// Creates a map field and the entry message that needs to be nested in the message.
+pub fn map_field(
+    parent_name : &str,
+    name : &str,
+    number : i32,
+    key : FieldType,
+    value : FieldType,
+) -> ( FieldDescriptorProto, DescriptorProto )
+{
+    let entry_name = format!( "{}Entry", inflector::cases::pascalcase::to_pascal_case( name ) );
+    let mut entry = message( &entry_name, vec![
+        field( "key", 1, key, None ),
+        field( "value", 2, value, None ),
+    ] );
+    entry.mut_options().set_map_entry( true );
+
+    let entry_type = format!( "{}.{}", parent_name, entry_name );
+    let mut field = field( name, number, FieldType::TYPE_MESSAGE, Some( &entry_type ) );
+    field.set_label( Label::LABEL_REPEATED );
+    ( field, entry )
+}
+
+pub fn enumeration( name : &str, values : &[&str] ) -> EnumDescriptorProto
+{
+    let mut e = EnumDescriptorProto::new();
+    e.set_name( name.to_string() );
+    for ( number, name ) in values.iter().enumerate() {
+        let mut value = EnumValueDescriptorProto::new();
+        value.set_name( name.to_string() );
+        value.set_number( number as i32 );
+        e.mut_value().push( value );
+    }
+    e
+}
+
+pub fn field(
+    name : &str,
+    number : i32,
+    field_type : FieldType,
+    type_name : Option<&str>,
+) -> FieldDescriptorProto
+{
+    let mut field = FieldDescriptorProto::new();
+    field.set_name( name.to_string() );
+    field.set_number( number );
+    field.set_field_type( field_type );
+    field.set_label( Label::LABEL_OPTIONAL );
+    if let Some( type_name ) = type_name {
+        field.set_type_name( type_name.to_string() );
+    }
+    field
+}
+
+/// Creates a proto file in the `test` package with messages covering the common field types.
+pub fn test_file() -> FileDescriptorProto
+{
+    let ( values, values_entry ) =
+            map_field( ".test.Item", "values", 5, FieldType::TYPE_STRING, FieldType::TYPE_INT32 );
+    let mut item = message( "Item", vec![
+        field( "name", 1, FieldType::TYPE_STRING, None ),
+        field( "count", 2, FieldType::TYPE_INT32, None ),
+        field( "size", 3, FieldType::TYPE_UINT32, None ),
+        field( "part", 4, FieldType::TYPE_MESSAGE, Some( ".test.Part" ) ),
+        values,
+        field( "kind", 6, FieldType::TYPE_ENUM, Some( ".test.Kind" ) ),
+        field( "ratio", 7, FieldType::TYPE_FLOAT, None ),
+    ] );
+    item.mut_nested_type().push( values_entry );
+
+    let part = message( "Part", vec![
+        field( "name", 1, FieldType::TYPE_STRING, None ),
+    ] );
+
+    let mut file = file( "test.proto", "test", vec![ item, part ] );
+    file.mut_enum_type().push( enumeration( "Kind", &[ "KIND_UNKNOWN", "KIND_A" ] ) );
+    file
+}
+
+/// Validates the spec against the proto files and returns the diagnostic messages.
+///
+/// All the proto files are generated.
+pub fn validate( files : Vec<FileDescriptorProto>, spec_source : &str ) -> Vec<String>
+{
+    let mut request = protos::plugin::CodeGeneratorRequest::new();
+    for file in &files {
+        request.mut_file_to_generate().push( file.get_name().to_string() );
+    }
+    request.set_proto_file( files.into() );
+
+    let spec_files = vec![ spec::parse( "test.spec", spec_source ).expect( "Invalid spec" ) ];
+    let options = HashMap::new();
+    let index = SymbolIndex::new( &request, &spec_files );
+    let context = PluginContext {
+        request: &request,
+        files: &spec_files,
+        index: &index,
+        options: &options,
+    };
+
+    match validation::validate( &context ) {
+        Ok(()) => vec![],
+        Err( diagnostics ) => diagnostics.into_iter().map( |d| d.message ).collect(),
+    }
+}
@@ -0,0 +1,582 @@
+
+use protobuf::descriptor::FieldDescriptorProto;
+use protobuf::descriptor::FieldDescriptorProto_Type as FieldType;
+use protobuf::descriptor::FieldDescriptorProto_Label;
+use crate::prelude::*;
+
+/// Type of a value within the constructor expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType
+{
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Bool,
+    String,
+    Bytes,
+
+    /// Enum type identified by its full name.
+    Enum( String ),
+
+    /// Message type identified by its full name.
+    Message( String ),
+
+    /// List of values for a repeated field.
+    Repeated( Box<ValueType> ),
+
+    /// Key-value pairs for a map field.
+    Map( Box<ValueType>, Box<ValueType> ),
+}
+
+/// Type inferred for an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferredType
+{
+    /// Value of a known type.
+    Value( ValueType ),
+
+    /// Integer literal that may be used with any numeric type it fits in.
+    Integer( i64 ),
+
+    /// Float literal that may be used with any floating point type it fits in.
+    Float( f64 ),
+
+    /// The type cannot be known, such as with stand-alone function calls.
+    ///
+    /// Unknown types are accepted anywhere.
+    Unknown,
+}
+
+impl ValueType
+{
+    /// Gets the value type of a message field.
+    pub fn of_field(
+        type_context : &TypeContext,
+        field : &FieldDescriptorProto
+    ) -> ValueType
+    {
+        // Map fields are repeated fields of synthesized entry messages that have the key and
+        // value fields.
+        //
+        // The entry fields belong to the entry message so they must not be looked up as map
+        // fields of the current message. Their names may match the name of the map field.
+        if let Some( entry ) = type_context.get_map_entry( field.get_name() ) {
+            let entry_field = |number| entry.get_field().iter()
+                .find( |f| f.get_number() == number )
+                .map( ValueType::of_plain_field );
+            if let ( Some( key ), Some( value ) ) = ( entry_field( 1 ), entry_field( 2 ) ) {
+                return ValueType::Map( Box::new( key ), Box::new( value ) );
+            }
+        }
+
+        ValueType::of_plain_field( field )
+    }
+
+    /// Gets the value type of a field without considering map fields.
+    fn of_plain_field(
+        field : &FieldDescriptorProto
+    ) -> ValueType
+    {
+        // The type names in the descriptors are absolute and start with a dot.
+        let type_name = field.get_type_name().trim_start_matches( '.' ).to_string();
+
+        let value_type = match field.get_field_type() {
+            FieldType::TYPE_DOUBLE => ValueType::Double,
+            FieldType::TYPE_FLOAT => ValueType::Float,
+            FieldType::TYPE_INT64
+                | FieldType::TYPE_SINT64
+                | FieldType::TYPE_SFIXED64 => ValueType::Int64,
+            FieldType::TYPE_UINT64
+                | FieldType::TYPE_FIXED64 => ValueType::Uint64,
+            FieldType::TYPE_INT32
+                | FieldType::TYPE_SINT32
+                | FieldType::TYPE_SFIXED32 => ValueType::Int32,
+            FieldType::TYPE_UINT32
+                | FieldType::TYPE_FIXED32 => ValueType::Uint32,
+            FieldType::TYPE_BOOL => ValueType::Bool,
+            FieldType::TYPE_STRING => ValueType::String,
+            FieldType::TYPE_BYTES => ValueType::Bytes,
+            FieldType::TYPE_ENUM => ValueType::Enum( type_name ),
+            FieldType::TYPE_MESSAGE
+                | FieldType::TYPE_GROUP => ValueType::Message( type_name ),
+        };
+
+        match field.get_label() {
+            FieldDescriptorProto_Label::LABEL_REPEATED => ValueType::Repeated( Box::new( value_type ) ),
+            _ => value_type,
+        }
+    }
+
+    /// Gets the value type of a constructor parameter.
+    ///
+    /// Custom types are resolved relative to the type the constructor belongs to. Returns `None`
+    /// if the custom type cannot be resolved.
+    pub fn of_param(
+        type_context : &TypeContext,
+        param_type : &spec::ParamType
+    ) -> Option<ValueType>
+    {
+        match param_type {
+            spec::ParamType::Double => Some( ValueType::Double ),
+            spec::ParamType::Float => Some( ValueType::Float ),
+            spec::ParamType::Int64
+                | spec::ParamType::Sint64
+                | spec::ParamType::Sfixed64 => Some( ValueType::Int64 ),
+            spec::ParamType::Uint64
+                | spec::ParamType::Fixed64 => Some( ValueType::Uint64 ),
+            spec::ParamType::Int32
+                | spec::ParamType::Sint32
+                | spec::ParamType::Sfixed32 => Some( ValueType::Int32 ),
+            spec::ParamType::Uint32
+                | spec::ParamType::Fixed32 => Some( ValueType::Uint32 ),
+            spec::ParamType::Bool => Some( ValueType::Bool ),
+            spec::ParamType::String => Some( ValueType::String ),
+            spec::ParamType::Bytes => Some( ValueType::Bytes ),
+            spec::ParamType::Custom( name ) => {
+                type_context.resolve_type( name )
+                    .map( |t| match t.type_descriptor {
+                        TypeDescriptor::Message( _ ) => ValueType::Message( t.full_name ),
+                        TypeDescriptor::Enum( _ ) => ValueType::Enum( t.full_name ),
+                    } )
+            },
+            spec::ParamType::Repeated( item ) => ValueType::of_param( type_context, item )
+                .map( |item| ValueType::Repeated( Box::new( item ) ) ),
+            spec::ParamType::Map( key, value ) => {
+                let key = ValueType::of_param( type_context, key )?;
+                let value = ValueType::of_param( type_context, value )?;
+                Some( ValueType::Map( Box::new( key ), Box::new( value ) ) )
+            },
+        }
+    }
+
+    /// Checks whether a value of the inferred type may be assigned to this type.
+    pub fn accepts( &self, inferred : &InferredType ) -> bool
+    {
+        match inferred {
+            InferredType::Unknown => true,
+            InferredType::Value( value_type ) => match ( self, value_type ) {
+
+                // Allow the lossless widening conversions both Java and JS support.
+                ( ValueType::Int64, ValueType::Int32 ) => true,
+                ( ValueType::Double, ValueType::Float ) => true,
+                ( a, b ) => a == b,
+            },
+            InferredType::Integer( i ) => match self {
+                ValueType::Int32 => *i >= i64::from( i32::MIN )
+                        && *i <= i64::from( i32::MAX ),
+                ValueType::Uint32 => *i >= 0 && *i <= i64::from( u32::MAX ),
+                ValueType::Int64 => true,
+                ValueType::Uint64 => *i >= 0,
+                ValueType::Float | ValueType::Double => true,
+                _ => false,
+            },
+            InferredType::Float( f ) => match self {
+                ValueType::Float => f.abs() <= f64::from( f32::MAX ),
+                ValueType::Double => true,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType
+{
+    fn fmt( &self, f : &mut std::fmt::Formatter ) -> std::fmt::Result
+    {
+        match self {
+            ValueType::Double => f.write_str( "double" ),
+            ValueType::Float => f.write_str( "float" ),
+            ValueType::Int32 => f.write_str( "int32" ),
+            ValueType::Int64 => f.write_str( "int64" ),
+            ValueType::Uint32 => f.write_str( "uint32" ),
+            ValueType::Uint64 => f.write_str( "uint64" ),
+            ValueType::Bool => f.write_str( "bool" ),
+            ValueType::String => f.write_str( "string" ),
+            ValueType::Bytes => f.write_str( "bytes" ),
+            ValueType::Enum( name ) => write!( f, "enum {}", name ),
+            ValueType::Message( name ) => write!( f, "message {}", name ),
+            ValueType::Repeated( item ) => write!( f, "repeated {}", item ),
+            ValueType::Map( key, value ) => write!( f, "map<{}, {}>", key, value ),
+        }
+    }
+}
+
+impl std::fmt::Display for InferredType
+{
+    fn fmt( &self, f : &mut std::fmt::Formatter ) -> std::fmt::Result
+    {
+        match self {
+            InferredType::Value( value_type ) => write!( f, "{}", value_type ),
+            InferredType::Integer( i ) => write!( f, "integer {}", i ),
+            InferredType::Float( value ) => write!( f, "float literal {:?}", value ),
+            InferredType::Unknown => f.write_str( "unknown" ),
+        }
+    }
+}
+
+/// Checks the constructor initializer values against the field types.
+///
+/// The parameter default values are checked against the parameter types.
+pub fn check_constructor(
+    type_context : &TypeContext,
+    file : &spec::File,
+    ctor : &spec::Constructor,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    for param in &ctor.params {
+
+        let default = match &param.default {
+            Some( default ) => default,
+            None => continue,
+        };
+
+        let actual = infer( type_context, file, ctor, default, diagnostics );
+        let expected = match ValueType::of_param( type_context, &param.param_type ) {
+            Some( expected ) => expected,
+            None => continue,
+        };
+
+        if ! expected.accepts( &actual ) {
+            diagnostics.push( Diagnostic::new(
+                    file, default.span,
+                    format!( "Mismatched types: parameter `{}` is `{}` but the default value is `{}`",
+                        param.name, expected, actual ) ) );
+        }
+    }
+
+    for initializer in &ctor.initializers {
+
+        // Unknown fields are reported by the validation so there's nothing to check here.
+        let field = match type_context.get_field( initializer.field ) {
+            Some( field ) => field,
+            None => continue,
+        };
+
+        let expected = ValueType::of_field( type_context, field );
+        let actual = infer( type_context, file, ctor, &initializer.value, diagnostics );
+        // The map entry types of plain repeated fields would make the generic mismatch message
+        // confusing so report maps assigned to other fields separately.
+        let is_map_value = matches!( actual, InferredType::Value( ValueType::Map( .. ) ) );
+        if is_map_value && ! matches!( expected, ValueType::Map( .. ) ) {
+            diagnostics.push( Diagnostic::new(
+                    file, initializer.value.span,
+                    format!( "Field `{}` is not a map field", initializer.field ) ) );
+            continue;
+        }
+
+        if ! expected.accepts( &actual ) {
+            diagnostics.push( Diagnostic::new(
+                    file, initializer.value.span,
+                    format!( "Mismatched types: field `{}` is `{}` but the value is `{}`",
+                        initializer.field, expected, actual ) ) );
+        }
+    }
+}
+
+/// Infers the type of an expression.
+///
+/// Problems within the nested expressions, such as call arguments, are reported as diagnostics.
+fn infer(
+    type_context : &TypeContext,
+    file : &spec::File,
+    ctor : &spec::Constructor,
+    expr : &spec::Expr,
+    diagnostics : &mut Vec<Diagnostic>
+) -> InferredType
+{
+    match &expr.kind {
+        spec::ExprKind::Bool( _ ) => InferredType::Value( ValueType::Bool ),
+        spec::ExprKind::Integer( i ) => InferredType::Integer( *i ),
+        spec::ExprKind::Float( f ) => InferredType::Float( *f ),
+        spec::ExprKind::String( raw ) => {
+
+            // The backends rely on the escapes being valid so ensure that here. The raw string
+            // starts after the opening quote.
+            if let Err( e ) = spec::unescape( raw ) {
+                let start = expr.span.start + 1 + e.offset;
+                diagnostics.push( Diagnostic::new(
+                        file, spec::Span { start, end: start + e.len },
+                        "Invalid escape sequence" ) );
+            }
+            InferredType::Value( ValueType::String )
+        },
+        spec::ExprKind::Bytes( bytes ) => {
+
+            // Only the escaped literals may be invalid. These start after the `b"` prefix.
+            if let Err( e ) = bytes.to_bytes() {
+                let start = expr.span.start + 2 + e.offset;
+                diagnostics.push( Diagnostic::new(
+                        file, spec::Span { start, end: start + e.len },
+                        "Invalid escape sequence" ) );
+            }
+            InferredType::Value( ValueType::Bytes )
+        },
+        spec::ExprKind::Enum( e ) => infer_enum( type_context, file, e, diagnostics ),
+
+        // Unknown parameters are reported by the validation.
+        spec::ExprKind::Ref( r ) => ctor.params.iter()
+            .find( |p| p.name == *r )
+            .and_then( |p| ValueType::of_param( type_context, &p.param_type ) )
+            .map( InferredType::Value )
+            .unwrap_or( InferredType::Unknown ),
+
+        spec::ExprKind::Call( c ) => {
+
+            // Stand-alone functions are defined outside the specs so their signatures are
+            // unknown. The arguments are still inferred to catch problems within them.
+            let type_name = match c.type_name {
+                Some( t ) => t,
+                None => {
+                    for arg in &c.args {
+                        infer( type_context, file, ctor, arg, diagnostics );
+                    }
+                    return InferredType::Unknown;
+                }
+            };
+
+            let target = match type_context.resolve_type( type_name ) {
+                Some( target ) => target,
+                None => {
+                    diagnostics.push( Diagnostic::new(
+                            file, c.span, format!( "Unknown type `{}`", type_name ) ) );
+                    return InferredType::Unknown;
+                }
+            };
+
+            let target_ctor = target.type_spec
+                .and_then( |s| s.constructors.iter().find( |t| t.name == c.func_name ) );
+            let target_ctor = match target_ctor {
+                Some( target_ctor ) => target_ctor,
+                None => {
+                    diagnostics.push( Diagnostic::new(
+                            file, c.span,
+                            format!( "Unknown constructor `{}::{}`", type_name, c.func_name ) ) );
+                    return InferredType::Unknown;
+                }
+            };
+
+            // The trailing arguments with default values may be omitted.
+            let max_args = target_ctor.params.len();
+            let min_args = target_ctor.params.iter().take_while( |p| p.default.is_none() ).count();
+            if c.args.len() < min_args || c.args.len() > max_args {
+                let expected = if min_args == max_args {
+                    count_noun( max_args, "argument", "arguments" )
+                } else {
+                    format!( "{} to {} arguments", min_args, max_args )
+                };
+                let given = match c.args.len() {
+                    1 => "1 was".to_string(),
+                    n => format!( "{} were", n ),
+                };
+                diagnostics.push( Diagnostic::new(
+                        file, c.span,
+                        format!( "`{}::{}` takes {} but {} given",
+                            type_name, c.func_name, expected, given ) ) );
+            }
+
+            // The parameter types are relative to the type that defines the constructor.
+            for ( param, arg ) in target_ctor.params.iter().zip( &c.args ) {
+
+                let actual = infer( type_context, file, ctor, arg, diagnostics );
+                let expected = match ValueType::of_param( &target, &param.param_type ) {
+                    Some( expected ) => expected,
+                    None => continue,
+                };
+
+                if ! expected.accepts( &actual ) {
+                    diagnostics.push( Diagnostic::new(
+                            file, arg.span,
+                            format!( "Mismatched types: parameter `{}` is `{}` but the value is `{}`",
+                                param.name, expected, actual ) ) );
+                }
+            }
+
+            match target.type_descriptor {
+                TypeDescriptor::Message( _ ) => InferredType::Value(
+                        ValueType::Message( target.full_name ) ),
+                TypeDescriptor::Enum( _ ) => InferredType::Unknown,
+            }
+        },
+    }
+}
+
+/// Infers the type of an enum value.
+///
+/// The enum is resolved relative to the current type and the value must exist in it.
+fn infer_enum(
+    type_context : &TypeContext,
+    file : &spec::File,
+    value : &spec::EnumValue,
+    diagnostics : &mut Vec<Diagnostic>
+) -> InferredType
+{
+    let target = match type_context.resolve_type( value.enum_name ) {
+        Some( target ) => target,
+        None => {
+            diagnostics.push( Diagnostic::new(
+                    file, value.span, format!( "Unknown type `{}`", value.enum_name ) ) );
+            return InferredType::Unknown;
+        }
+    };
+
+    let descriptor = match target.type_descriptor {
+        TypeDescriptor::Enum( e ) => e,
+        TypeDescriptor::Message( _ ) => {
+            diagnostics.push( Diagnostic::new(
+                    file, value.span,
+                    format!( "`{}` is a message, not an enum", value.enum_name ) ) );
+            return InferredType::Unknown;
+        }
+    };
+
+    if ! descriptor.get_value().iter().any( |v| v.get_name() == value.value_name ) {
+        diagnostics.push( Diagnostic::new(
+                file, value.span,
+                format!( "Unknown value `{}` in enum `{}`", value.value_name, target.full_name ) ) );
+    }
+
+    InferredType::Value( ValueType::Enum( target.full_name ) )
+}
+
+/// Formats a count with the singular or plural form of the noun.
+fn count_noun( count : usize, singular : &str, plural : &str ) -> String
+{
+    if count == 1 {
+        format!( "1 {}", singular )
+    } else {
+        format!( "{} {}", count, plural )
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::test_utils::{test_file, validate};
+
+    /// Validates a spec with the given constructors for `Item` and a single `Part::Create`.
+    fn check_item( constructors : &str ) -> Vec<String>
+    {
+        let spec = format!( "
+            package test
+            message Item
+            {{
+                {}
+            }}
+            message Part
+            {{
+                Create( string name ) {{ name = name }}
+            }}
+        ", constructors );
+        validate( vec![ test_file() ], &spec )
+    }
+
+    #[test]
+    fn int32_range()
+    {
+        assert_eq!( check_item( "Create() { count = 2147483647 }" ), Vec::<String>::new() );
+        assert_eq!( check_item( "Create() { count = -2147483648 }" ), Vec::<String>::new() );
+        assert_eq!( check_item( "Create() { count = 2147483648 }" ), vec![
+            "Mismatched types: field `count` is `int32` but the value is `integer 2147483648`",
+        ] );
+        assert_eq!( check_item( "Create() { count = -2147483649 }" ), vec![
+            "Mismatched types: field `count` is `int32` but the value is `integer -2147483649`",
+        ] );
+    }
+
+    #[test]
+    fn uint32_range()
+    {
+        assert_eq!( check_item( "Create() { size = 4294967295 }" ), Vec::<String>::new() );
+        assert_eq!( check_item( "Create() { size = 4294967296 }" ), vec![
+            "Mismatched types: field `size` is `uint32` but the value is `integer 4294967296`",
+        ] );
+        assert_eq!( check_item( "Create() { size = -1 }" ), vec![
+            "Mismatched types: field `size` is `uint32` but the value is `integer -1`",
+        ] );
+    }
+
+    #[test]
+    fn float_range()
+    {
+        assert_eq!( check_item( "Create() { ratio = 0.5 }" ), Vec::<String>::new() );
+        assert_eq!( check_item( "Create() { ratio = 1 }" ), Vec::<String>::new() );
+        assert_eq!( check_item( "Create() { ratio = 1000000000000000000000000000000000000000.0 }" ), vec![
+            "Mismatched types: field `ratio` is `float` but the value is `float literal 1e39`",
+        ] );
+    }
+
+    #[test]
+    fn string_to_int32()
+    {
+        assert_eq!( check_item( "Create( string name ) { count = name }" ), vec![
+            "Mismatched types: field `count` is `int32` but the value is `string`",
+        ] );
+        assert_eq!( check_item( "Create() { count = \"1\" }" ), vec![
+            "Mismatched types: field `count` is `int32` but the value is `string`",
+        ] );
+    }
+
+    #[test]
+    fn wrong_message_from_call()
+    {
+        assert_eq!( check_item( "Create() { part = Item::Empty() } Empty() { }" ), vec![
+            "Mismatched types: field `part` is `message test.Part` but the value is `message test.Item`",
+        ] );
+    }
+
+    #[test]
+    fn argument_count()
+    {
+        assert_eq!( check_item( "Create() { part = Part::Create() }" ), vec![
+            "`Part::Create` takes 1 argument but 0 were given",
+        ] );
+        assert_eq!( check_item( "Create() { part = Part::Create( \"a\", \"b\" ) }" ), vec![
+            "`Part::Create` takes 1 argument but 2 were given",
+        ] );
+        assert_eq!( check_item( "Create() { part = Part::Create( \"a\" ) }" ), Vec::<String>::new() );
+    }
+
+    #[test]
+    fn argument_count_with_defaults()
+    {
+        let check_call = |call : &str| validate( vec![ test_file() ], &format!( "
+            package test
+            message Item
+            {{
+                Create() {{ part = {} }}
+            }}
+            message Part
+            {{
+                Named( string name, int32 count = 1, uint32 size = 2 ) {{ name = name }}
+                Empty() {{ }}
+            }}
+        ", call ) );
+
+        assert_eq!( check_call( "Part::Named( \"a\" )" ), Vec::<String>::new() );
+        assert_eq!( check_call( "Part::Named( \"a\", 1, 2 )" ), Vec::<String>::new() );
+        assert_eq!( check_call( "Part::Named()" ), vec![
+            "`Part::Named` takes 1 to 3 arguments but 0 were given",
+        ] );
+        assert_eq!( check_call( "Part::Named( \"a\", 1, 2, 3 )" ), vec![
+            "`Part::Named` takes 1 to 3 arguments but 4 were given",
+        ] );
+        assert_eq!( check_call( "Part::Empty( \"a\" )" ), vec![
+            "`Part::Empty` takes 0 arguments but 1 was given",
+        ] );
+    }
+
+    #[test]
+    fn unknown_enum_value()
+    {
+        assert_eq!( check_item( "Create() { kind = Kind::KIND_B }" ), vec![
+            "Unknown value `KIND_B` in enum `test.Kind`",
+        ] );
+        assert_eq!( check_item( "Create() { kind = Part::KIND_A }" ), vec![
+            "`Part` is a message, not an enum",
+        ] );
+    }
+}
@@ -0,0 +1,357 @@
+
+use std::collections::HashSet;
+use crate::prelude::*;
+use crate::symbols;
+use crate::type_check;
+
+/// Validates the constructor specifications against the proto descriptors.
+///
+/// All the problems are collected and reported at once so the user doesn't need to fix them one
+/// plugin run at a time.
+pub fn validate(
+    context : &PluginContext
+) -> Result<(), Vec<Diagnostic>>
+{
+    let mut diagnostics = vec![];
+    // The same specs may be shared between protoc runs over different protos. Only the specs in
+    // the packages of the generated files are expected to match the messages of the request.
+    let generated_packages = context.iter_generated_files()
+        .into_iter()
+        .map( |f| f.descriptor.get_package() )
+        .collect::<HashSet<_>>();
+    for file in context.files {
+        let report_unknown = generated_packages.contains( file.package );
+        validate_spec_types(
+            context, file, file.package, &file.types, report_unknown, &mut diagnostics );
+    }
+
+    for ( type_context, type_spec ) in context.iter_generated_types() {
+
+        let file = type_context.spec_file.expect( "Generated types always have a spec file" );
+        for ctor in &type_spec.constructors {
+            validate_constructor( &type_context, file, ctor, &mut diagnostics );
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err( diagnostics )
+    }
+}
+
+/// Ensures each type specification matches a proto message and is the only specification for it.
+///
+/// Specifications that don't match a message would otherwise be ignored silently. These are
+/// reported only if `report_unknown` is set.
+fn validate_spec_types(
+    context : &PluginContext,
+    file : &spec::File,
+    parent_name : &str,
+    types : &[spec::Type],
+    report_unknown : bool,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    for t in types {
+
+        let full_name = symbols::join_name( parent_name, &t.name );
+        let indexed = match context.index.get_type( &full_name ) {
+            Some( indexed ) => indexed,
+            None => {
+                // The nested types of an unknown message would all be unknown as well so report
+                // only the outermost one.
+                if report_unknown {
+                    diagnostics.push( Diagnostic::new(
+                            file, t.span, format!( "Unknown message `{}`", full_name ) ) );
+                }
+                continue;
+            }
+        };
+
+        if let TypeDescriptor::Enum( _ ) = indexed.descriptor {
+            diagnostics.push( Diagnostic::new(
+                    file, t.span, format!( "`{}` is an enum, not a message", full_name ) ) );
+            continue;
+        }
+
+        // The index keeps the first specification for each message.
+        let is_indexed = matches!( indexed.spec, Some( ( _, s ) ) if std::ptr::eq( s, t ) );
+        if ! is_indexed {
+            diagnostics.push( Diagnostic::new(
+                    file, t.span,
+                    format!( "Message `{}` is specified more than once", full_name ) ) );
+        }
+
+        validate_spec_types(
+            context, file, &full_name, &t.nested_types, report_unknown, diagnostics );
+    }
+}
+
+/// Validates the initializers of a single constructor.
+fn validate_constructor(
+    type_context : &TypeContext,
+    file : &spec::File,
+    ctor : &spec::Constructor,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    // Parameters with default values must come after all the required parameters so the callers
+    // are able to omit them.
+    let mut has_default = false;
+    for param in &ctor.params {
+        validate_param_type( type_context, file, param, &param.param_type, diagnostics );
+
+        match &param.default {
+            Some( default ) => {
+                has_default = true;
+                validate_default( file, param, default, diagnostics );
+            },
+            None if has_default => {
+                diagnostics.push( Diagnostic::new(
+                        file, param.span,
+                        format!( "Parameter `{}` without a default value follows parameters with default values",
+                            param.name ) ) );
+            },
+            None => {},
+        }
+    }
+
+    let mut initialized = HashSet::new();
+    for initializer in &ctor.initializers {
+
+        // The field must exist on the message.
+        if type_context.get_field( initializer.field ).is_none() {
+            diagnostics.push( Diagnostic::new(
+                    file, initializer.field_span(),
+                    format!( "Unknown field `{}` in message `{}`",
+                        initializer.field, type_context.full_name ) ) );
+        }
+
+        // Each field may be set only once.
+        if ! initialized.insert( initializer.field ) {
+            diagnostics.push( Diagnostic::new(
+                    file, initializer.field_span(),
+                    format!( "Field `{}` is initialized more than once in `{}`",
+                        initializer.field, ctor.name ) ) );
+        }
+
+        validate_refs( file, ctor, &initializer.value, diagnostics );
+    }
+
+    type_check::check_constructor( type_context, file, ctor, diagnostics );
+}
+
+/// Ensures the parameter type is valid.
+fn validate_param_type(
+    type_context : &TypeContext,
+    file : &spec::File,
+    param : &spec::Parameter,
+    param_type : &spec::ParamType,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    match param_type {
+
+        // Custom types must name a message or an enum visible from the current type.
+        spec::ParamType::Custom( name ) if type_context.resolve_type( name ).is_none() => {
+            diagnostics.push( Diagnostic::new(
+                    file, param.span,
+                    format!( "Unknown type `{}` for parameter `{}`", name, param.name ) ) );
+        },
+        spec::ParamType::Repeated( item ) => {
+            validate_param_type( type_context, file, param, item, diagnostics );
+        },
+        spec::ParamType::Map( key, value ) => {
+
+            // Protobuf allows only integral and string map keys.
+            let valid_key = ! matches!( **key,
+                    spec::ParamType::Double
+                    | spec::ParamType::Float
+                    | spec::ParamType::Bytes
+                    | spec::ParamType::Custom( _ ) );
+            if ! valid_key {
+                diagnostics.push( Diagnostic::new(
+                        file, param.span,
+                        format!( "Invalid map key type for parameter `{}`", param.name ) ) );
+            }
+            validate_param_type( type_context, file, param, value, diagnostics );
+        },
+        _ => {},
+    }
+}
+
+/// Ensures the default value doesn't reference any parameters.
+///
+/// The defaults are evaluated when the argument is omitted so there are no parameter values
+/// available for them.
+fn validate_default(
+    file : &spec::File,
+    param : &spec::Parameter,
+    expr : &spec::Expr,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    match &expr.kind {
+        spec::ExprKind::Ref( r ) => {
+            diagnostics.push( Diagnostic::new(
+                    file, expr.span,
+                    format!( "Default value of `{}` cannot reference parameter `{}`",
+                        param.name, r ) ) );
+        },
+        spec::ExprKind::Call( c ) => {
+            for arg in &c.args {
+                validate_default( file, param, arg, diagnostics );
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Ensures all the references within the expression name constructor parameters.
+fn validate_refs(
+    file : &spec::File,
+    ctor : &spec::Constructor,
+    expr : &spec::Expr,
+    diagnostics : &mut Vec<Diagnostic>
+)
+{
+    match &expr.kind {
+        spec::ExprKind::Ref( r ) if ! ctor.params.iter().any( |p| p.name == *r ) => {
+            diagnostics.push( Diagnostic::new(
+                    file, expr.span,
+                    format!( "Unknown parameter `{}` in `{}`", r, ctor.name ) ) );
+        },
+        spec::ExprKind::Call( c ) => {
+            for arg in &c.args {
+                validate_refs( file, ctor, arg, diagnostics );
+            }
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::test_utils::{test_file, validate};
+
+    #[test]
+    fn valid_spec()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( string name, map<string, int32> values, int32 count = 1 )
+                {
+                    name = name
+                    count = count
+                    part = Part::Create( name )
+                    values = values
+                    kind = Kind::KIND_A
+                }
+            }
+            message Part
+            {
+                Create( string name ) { name = name }
+            }
+        " );
+        assert_eq!( diagnostics, Vec::<String>::new() );
+    }
+
+    #[test]
+    fn unknown_field()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( string name ) { title = name }
+            }
+        " );
+        assert_eq!( diagnostics, vec![ "Unknown field `title` in message `test.Item`" ] );
+    }
+
+    #[test]
+    fn duplicate_initializer()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( string name )
+                {
+                    name = name
+                    name = \"other\"
+                }
+            }
+        " );
+        assert_eq!( diagnostics, vec![ "Field `name` is initialized more than once in `Create`" ] );
+    }
+
+    #[test]
+    fn unknown_parameter_ref()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( string name ) { name = title }
+            }
+        " );
+        assert_eq!( diagnostics, vec![ "Unknown parameter `title` in `Create`" ] );
+    }
+
+    #[test]
+    fn default_before_required()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( int32 count = 1, string name )
+                {
+                    name = name
+                    count = count
+                }
+            }
+        " );
+        assert_eq!( diagnostics, vec![
+            "Parameter `name` without a default value follows parameters with default values",
+        ] );
+    }
+
+    #[test]
+    fn invalid_map_key()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Item
+            {
+                Create( map<double, int32> values ) { }
+            }
+        " );
+        assert_eq!( diagnostics, vec![ "Invalid map key type for parameter `values`" ] );
+    }
+
+    #[test]
+    fn unknown_message()
+    {
+        let diagnostics = validate( vec![ test_file() ], "
+            package test
+            message Missing
+            {
+                Create() { }
+            }
+            message Kind
+            {
+                Create() { }
+            }
+        " );
+        assert_eq!( diagnostics, vec![
+            "Unknown message `test.Missing`",
+            "`test.Kind` is an enum, not a message",
+        ] );
+    }
+}
@@ -0,0 +1,12 @@
+package net.jubjubnest.protocGenConstructors.test;
+
+/**
+ * Stand-alone functions used by the constructors.
+ */
+public class Helpers
+{
+    public static String defaultName()
+    {
+        return "default";
+    }
+}
@@ -1,9 +1,23 @@
 package net.jubjubnest.protocGenConstructors.test;
 
+import static org.junit.Assert.assertArrayEquals;
 import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.fail;
+
+import java.util.Arrays;
+import java.util.HashMap;
+import java.util.Map;
 
 import org.junit.Test;
+import com.google.protobuf.Timestamp;
+import net.jubjubnest.ObjectInfoConstructors;
+import net.jubjubnest.ObjectInfoOwnerConstructors;
 import net.jubjubnest.Protos;
+import net.jubjubnest.conflict.ConflictOuterClass;
+import net.jubjubnest.naming.NamingRules2X;
+import net.jubjubnest.multi.Item;
+import net.jubjubnest.testing.Testing;
+import net.test.Test2;
 
 public class AppTest 
 {
@@ -14,4 +28,215 @@ public class AppTest
         assertEquals(1, objid.getTypeId());
         assertEquals(2, objid.getItemId().getInternalId());
     }
+
+    @Test
+    public void shouldOmitDefaultArgumentsInNestedConstructors()
+    {
+        Protos.ObjectId objid = Protos.ObjectId.external(1, "foo");
+        assertEquals(1, objid.getTypeId());
+        assertEquals("foo", objid.getItemId().getExternalId());
+        assertEquals(0, objid.getItemId().getInternalId());
+    }
+
+    @Test
+    public void shouldWorkWithoutParameters()
+    {
+        Protos.ObjectId objid = Protos.ObjectId.unknown();
+        assertEquals(0, objid.getTypeId());
+        assertEquals(false, objid.hasItemId());
+    }
+
+    @Test
+    public void shouldUseDefaultValues()
+    {
+        Protos.ObjectItemId itemid = Protos.ObjectItemId.external("foo");
+        assertEquals("foo", itemid.getExternalId());
+        assertEquals(0, itemid.getInternalId());
+
+        itemid = Protos.ObjectItemId.external("foo", 2);
+        assertEquals("foo", itemid.getExternalId());
+        assertEquals(2, itemid.getInternalId());
+    }
+
+    @Test
+    public void shouldSetStringLiterals()
+    {
+        Protos.ObjectItemId itemid = Protos.ObjectItemId.legacy(2);
+        assertEquals("legacy \"id\"\n", itemid.getExternalId());
+        assertEquals(2, itemid.getInternalId());
+    }
+
+    @Test
+    public void shouldSetRepeatedFields()
+    {
+        Protos.ObjectIdList list = Protos.ObjectIdList.create(Arrays.asList(
+                Protos.ObjectId.internal(1, 2),
+                Protos.ObjectId.internal(3, 4)));
+        assertEquals(2, list.getIdsCount());
+        assertEquals(3, list.getIds(1).getTypeId());
+    }
+
+    @Test
+    public void shouldSetMapFields()
+    {
+        Map<String, Protos.ObjectId> ids = new HashMap<>();
+        ids.put("a", Protos.ObjectId.internal(1, 2));
+
+        Protos.ObjectIdMap map = Protos.ObjectIdMap.create(ids);
+        assertEquals(1, map.getIdsCount());
+        assertEquals(1, map.getIdsOrThrow("a").getTypeId());
+    }
+
+    @Test
+    public void shouldSetBytesLiterals()
+    {
+        Protos.ObjectData data = Protos.ObjectData.magic();
+        assertArrayEquals(
+                new byte[] { (byte) 0xca, (byte) 0xfe, (byte) 0xba, (byte) 0xbe },
+                data.getData().toByteArray());
+
+        data = Protos.ObjectData.header();
+        assertArrayEquals(
+                new byte[] { 0x69, 0x64, 0x00, (byte) 0xff },
+                data.getData().toByteArray());
+    }
+
+    @Test
+    public void shouldConstructNestedMessages()
+    {
+        Protos.ObjectInfo info = Protos.ObjectInfo.create("foo");
+        assertEquals("foo", info.getOwner().getName());
+
+        Protos.ObjectInfo.Owner owner = Protos.ObjectInfo.Owner.create("bar");
+        assertEquals("bar", owner.getName());
+    }
+
+    @Test
+    public void shouldSetEnumValues()
+    {
+        Protos.ObjectInfo info = Protos.ObjectInfo.archived("foo");
+        assertEquals(Protos.ObjectStatus.OBJECT_STATUS_ARCHIVED, info.getStatus());
+        assertEquals(Protos.ObjectInfo.Visibility.PRIVATE, info.getVisibility());
+
+        info = Protos.ObjectInfo.archived("foo", Protos.ObjectInfo.Visibility.PUBLIC);
+        assertEquals(Protos.ObjectInfo.Visibility.PUBLIC, info.getVisibility());
+    }
+
+    @Test
+    public void shouldAcceptQualifiedTypes()
+    {
+        Timestamp created = Timestamp.newBuilder().setSeconds(10).build();
+        Protos.ObjectInfo info = Protos.ObjectInfo.created("foo", created);
+        assertEquals("foo", info.getOwner().getName());
+        assertEquals(Protos.ObjectInfo.Visibility.PUBLIC, info.getVisibility());
+        assertEquals(10, info.getCreated().getSeconds());
+
+        Test2.Struct struct = Test2.Struct.archived(1, 2);
+        assertEquals(1, struct.getA());
+        assertEquals(Protos.ObjectStatus.OBJECT_STATUS_ARCHIVED, struct.getStatus());
+    }
+
+    @Test
+    public void shouldResolveTypesLikeProtoc()
+    {
+        Testing.Reference ref = Testing.Reference.create(1, "foo", 2, 3);
+        assertEquals(1, ref.getLocal().getValue());
+        assertEquals("foo", ref.getShared().getValue());
+        assertEquals(2, ref.getRemote().getTypeId());
+        assertEquals(3, ref.getRemote().getItemId().getInternalId());
+    }
+
+    @Test
+    public void shouldSupportMultipleFiles()
+    {
+        Item item = Item.create("foo", "bar");
+        assertEquals("foo", item.getName());
+        assertEquals("bar", item.getPart().getName());
+
+        Item.Part part = Item.Part.create("baz");
+        assertEquals("baz", part.getName());
+    }
+
+    @Test
+    public void shouldUseDefaultOuterClassNames()
+    {
+        NamingRules2X.Name name = NamingRules2X.Name.create("foo");
+        assertEquals("foo", name.getValue());
+
+        ConflictOuterClass.Conflict conflict = ConflictOuterClass.Conflict.create("bar");
+        assertEquals("bar", conflict.getValue());
+    }
+
+    @Test
+    public void shouldCallHelperFunctions()
+    {
+        NamingRules2X.Name name = NamingRules2X.Name.default_();
+        assertEquals("default", name.getValue());
+    }
+
+    @Test
+    public void shouldConstructWithStandaloneClasses()
+    {
+        Protos.ObjectInfo info = ObjectInfoConstructors.archived("foo");
+        assertEquals("foo", info.getOwner().getName());
+        assertEquals(Protos.ObjectInfo.Visibility.PRIVATE, info.getVisibility());
+
+        Protos.ObjectInfo.Builder builder = ObjectInfoConstructors.createBuilder("bar");
+        assertEquals("bar", builder.getOwner().getName());
+
+        Protos.ObjectInfo.Owner owner = ObjectInfoOwnerConstructors.create("baz");
+        assertEquals("baz", owner.getName());
+    }
+
+    @Test
+    public void shouldRejectNullParameters()
+    {
+        try {
+            Protos.ObjectInfo.archived(null);
+            fail("Expected a NullPointerException");
+        } catch (NullPointerException e) {
+            assertEquals("owner", e.getMessage());
+        }
+    }
+
+    @Test
+    public void shouldApplyConstructorsToExistingBuilders()
+    {
+        Protos.ObjectInfo info = Protos.ObjectInfo.newBuilder()
+                .setVisibility(Protos.ObjectInfo.Visibility.PUBLIC)
+                .applyCreate("foo")
+                .build();
+        assertEquals("foo", info.getOwner().getName());
+        assertEquals(Protos.ObjectInfo.Visibility.PUBLIC, info.getVisibility());
+
+        Protos.ObjectInfo.Builder builder = Protos.ObjectInfo.newBuilder()
+                .setVisibility(Protos.ObjectInfo.Visibility.PUBLIC);
+        info = ObjectInfoConstructors.applyCreate(builder, "bar").build();
+        assertEquals("bar", info.getOwner().getName());
+        assertEquals(Protos.ObjectInfo.Visibility.PUBLIC, info.getVisibility());
+    }
+
+    @Test
+    public void shouldUseLiteralsOfTheFieldTypes()
+    {
+        Protos.ObjectStats stats = Protos.ObjectStats.large();
+        assertEquals(3000000000L, stats.getSize());
+        assertEquals((int) 4000000000L, stats.getChecksum());
+        assertEquals(0.5f, stats.getRatio(), 0);
+        assertEquals(2d, stats.getScore(), 0);
+
+        stats = Protos.ObjectStats.unit();
+        assertEquals(1f, stats.getRatio(), 0);
+        assertEquals(1.5, stats.getScore(), 0);
+    }
+
+    @Test
+    public void shouldSetMapFieldsNamedValue()
+    {
+        Map<String, Integer> values = new HashMap<String, Integer>();
+        values.put("a", 1);
+
+        Protos.ObjectCounters counters = Protos.ObjectCounters.create(values);
+        assertEquals(Integer.valueOf(1), counters.getValueMap().get("a"));
+    }
 }
@@ -12,10 +12,12 @@ function build(cb) {
     exec( './node_modules/protoc/protoc/bin/protoc ' +
         '--plugin=../../target/debug/protoc-gen-js_constructors ' +
         '--js_out=import_style=commonjs:build ' +
-        '--js_constructors_out=../protos/test1.spec,../protos/test2.spec:build ' +
+        '--js_constructors_out=../protos/test1.spec,../protos/test2.spec,../protos/test3.spec,../protos/test4.spec:build ' +
         '-I../protos ' +
         '../protos/test1.proto ' +
-        '../protos/test2.proto', cb );
+        '../protos/test2.proto ' +
+        '../protos/test3.proto ' +
+        '../protos/test4.proto', cb );
 }
 
 exports.clean = clean;
@@ -1,12 +1,19 @@
 
 require('mocha');
 const expect = require('chai').expect;
+const execFileSync = require('child_process').execFileSync;
+const path = require('path');
 
 const test1 = require('../build/test1_pb');
 const test2 = require('../build/test2_pb');
+const test3 = require('../build/test3_pb');
+const test4 = require('../build/test4_pb');
+const timestamp = require('google-protobuf/google/protobuf/timestamp_pb');
 
 require('../build/test1_pb-constructors');
 require('../build/test2_pb-constructors');
+require('../build/test3_pb-constructors');
+require('../build/test4_pb-constructors');
 
 describe('ObjectId', () => {
     describe('#internal()', () => {
@@ -18,4 +25,241 @@ describe('ObjectId', () => {
             expect(objid.getItemId().getInternalId()).to.equal(2);
         });
     });
+
+    describe('#external()', () => {
+        it('should omit default arguments in nested constructors', () => {
+
+            let objid = test1.ObjectId.external(1, 'foo');
+
+            expect(objid.getTypeId()).to.equal(1);
+            expect(objid.getItemId().getExternalId()).to.equal('foo');
+            expect(objid.getItemId().getInternalId()).to.equal(0);
+        });
+    });
+
+    describe('#unknown()', () => {
+        it('should work without parameters', () => {
+
+            let objid = test1.ObjectId.unknown();
+
+            expect(objid.getTypeId()).to.equal(0);
+            expect(objid.hasItemId()).to.equal(false);
+        });
+    });
+});
+
+describe('ObjectItemId', () => {
+    describe('#external()', () => {
+        it('should use default values for omitted arguments', () => {
+
+            let itemid = test1.ObjectItemId.external('foo');
+
+            expect(itemid.getExternalId()).to.equal('foo');
+            expect(itemid.getInternalId()).to.equal(0);
+        });
+
+        it('should use given arguments', () => {
+
+            let itemid = test1.ObjectItemId.external('foo', 2);
+
+            expect(itemid.getExternalId()).to.equal('foo');
+            expect(itemid.getInternalId()).to.equal(2);
+        });
+    });
+
+    describe('#legacy()', () => {
+        it('should set string literals', () => {
+
+            let itemid = test1.ObjectItemId.legacy(2);
+
+            expect(itemid.getExternalId()).to.equal('legacy "id"\n');
+            expect(itemid.getInternalId()).to.equal(2);
+        });
+    });
+});
+
+describe('ObjectIdList', () => {
+    describe('#create()', () => {
+        it('should set repeated fields', () => {
+
+            let list = test1.ObjectIdList.create([
+                test1.ObjectId.internal(1,2),
+                test1.ObjectId.internal(3,4),
+            ]);
+
+            expect(list.getIdsList().length).to.equal(2);
+            expect(list.getIdsList()[1].getTypeId()).to.equal(3);
+        });
+    });
+});
+
+describe('ObjectIdMap', () => {
+    describe('#create()', () => {
+        it('should set map fields', () => {
+
+            let map = test1.ObjectIdMap.create(new Map([
+                ['a', test1.ObjectId.internal(1,2)],
+            ]));
+
+            expect(map.getIdsMap().getLength()).to.equal(1);
+            expect(map.getIdsMap().get('a').getTypeId()).to.equal(1);
+        });
+    });
+});
+
+describe('ObjectData', () => {
+    describe('#magic()', () => {
+        it('should set hex bytes literals', () => {
+
+            let data = test1.ObjectData.magic();
+
+            expect(Array.from(data.getData_asU8())).to.deep.equal([0xca, 0xfe, 0xba, 0xbe]);
+        });
+    });
+
+    describe('#header()', () => {
+        it('should set escaped bytes literals', () => {
+
+            let data = test1.ObjectData.header();
+
+            expect(Array.from(data.getData_asU8())).to.deep.equal([0x69, 0x64, 0x00, 0xff]);
+        });
+    });
+});
+
+describe('ObjectInfo', () => {
+    describe('#create()', () => {
+        it('should construct nested messages', () => {
+
+            let info = test1.ObjectInfo.create('foo');
+
+            expect(info.getOwner().getName()).to.equal('foo');
+        });
+    });
+
+    describe('#archived()', () => {
+        it('should set enum values', () => {
+
+            let info = test1.ObjectInfo.archived('foo');
+
+            expect(info.getStatus()).to.equal(test1.ObjectStatus.OBJECT_STATUS_ARCHIVED);
+            expect(info.getVisibility()).to.equal(test1.ObjectInfo.Visibility.PRIVATE);
+        });
+
+        it('should accept enum arguments', () => {
+
+            let info = test1.ObjectInfo.archived('foo', test1.ObjectInfo.Visibility.PUBLIC);
+
+            expect(info.getVisibility()).to.equal(test1.ObjectInfo.Visibility.PUBLIC);
+        });
+    });
+
+    describe('#created()', () => {
+        it('should accept qualified types', () => {
+
+            let created = new timestamp.Timestamp();
+            created.setSeconds(10);
+
+            let info = test1.ObjectInfo.created('foo', created);
+
+            expect(info.getOwner().getName()).to.equal('foo');
+            expect(info.getVisibility()).to.equal(test1.ObjectInfo.Visibility.PUBLIC);
+            expect(info.getCreated().getSeconds()).to.equal(10);
+        });
+    });
+
+    describe('Owner', () => {
+        describe('#create()', () => {
+            it('should be defined on the nested type', () => {
+
+                let owner = test1.ObjectInfo.Owner.create('foo');
+
+                expect(owner.getName()).to.equal('foo');
+            });
+        });
+    });
+});
+
+describe('Struct', () => {
+    describe('#archived()', () => {
+        it('should set enum values from other files', () => {
+
+            let struct = test2.Struct.archived(1, 2);
+
+            expect(struct.getA()).to.equal(1);
+            expect(struct.getStatus()).to.equal(test1.ObjectStatus.OBJECT_STATUS_ARCHIVED);
+        });
+    });
+});
+
+describe('Reference', () => {
+    describe('#create()', () => {
+        it('should resolve types the same way as protoc', () => {
+
+            let ref = test3.Reference.create(1, 'foo', 2, 3);
+
+            expect(ref.getLocal()).to.be.an.instanceof(test3.Reference.ObjectId);
+            expect(ref.getLocal().getValue()).to.equal(1);
+            expect(ref.getShared()).to.be.an.instanceof(test3.ObjectId);
+            expect(ref.getShared().getValue()).to.equal('foo');
+            expect(ref.getRemote()).to.be.an.instanceof(test1.ObjectId);
+            expect(ref.getRemote().getItemId().getInternalId()).to.equal(3);
+        });
+
+        it('should load the constructors of the other files it calls', () => {
+
+            // The constructor modules are cached once required so load the module in a separate
+            // process to ensure it doesn't depend on the modules required above.
+            let script = `
+                const test3 = require('./build/test3_pb');
+                require('./build/test3_pb-constructors');
+                let ref = test3.Reference.create(1, 'foo', 2, 3);
+                process.stdout.write(String(ref.getRemote().getItemId().getInternalId()));
+            `;
+            let output = execFileSync(process.execPath, ['-e', script], {
+                cwd: path.join(__dirname, '..'),
+            });
+
+            expect(output.toString()).to.equal('3');
+        });
+    });
+});
+
+describe('Item', () => {
+    describe('#create()', () => {
+        it('should construct items', () => {
+
+            let item = test4.Item.create('foo', 'bar');
+
+            expect(item.getName()).to.equal('foo');
+            expect(item.getPart().getName()).to.equal('bar');
+        });
+    });
+});
+
+describe('ObjectStats', () => {
+    describe('#large()', () => {
+        it('should set values outside the 32-bit range', () => {
+
+            let stats = test1.ObjectStats.large();
+
+            expect(stats.getSize()).to.equal(3000000000);
+            expect(stats.getChecksum()).to.equal(4000000000);
+            expect(stats.getRatio()).to.equal(0.5);
+            expect(stats.getScore()).to.equal(2);
+        });
+    });
+});
+
+describe('ObjectCounters', () => {
+    describe('#create()', () => {
+        it('should set map fields named value', () => {
+
+            let counters = test1.ObjectCounters.create(new Map([
+                ['a', 1],
+            ]));
+
+            expect(counters.getValueMap().get('a')).to.equal(1);
+        });
+    });
 });
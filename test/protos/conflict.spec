@@ -0,0 +1,10 @@
+package jubjubnest.conflict
+
+message Conflict
+{
+    // Constructs a conflict with the given value.
+    Create( string value )
+    {
+        value = value
+    }
+}
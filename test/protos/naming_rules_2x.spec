@@ -0,0 +1,16 @@
+package jubjubnest.naming
+
+message Name
+{
+    // Constructs a name with the given value.
+    Create( string value )
+    {
+        value = value
+    }
+
+    // Constructs a name with the default value.
+    Default()
+    {
+        value = default_name()
+    }
+}
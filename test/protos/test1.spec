@@ -15,6 +15,22 @@ message ObjectId
         type_id = type
         item_id = ObjectItemId::Internal( item_id )
     }
+
+    // Constructs an item info that references an external object.
+    External(
+        // Object type.
+        int32 type,
+
+        // External object ID.
+        string external_id
+    )
+    {
+        type_id = type
+        item_id = ObjectItemId::External( external_id )
+    }
+
+    // Constructs an object ID that references no object.
+    Unknown() { }
 }
 
 message ObjectItemId
@@ -24,4 +40,137 @@ message ObjectItemId
     {
         internal_id = item_id
     }
+
+    // Constructs an item info that references an external object.
+    External(
+        // External object ID.
+        string external_id,
+
+        // Internal object ID if the object has one.
+        int32 internal_id = 0
+    )
+    {
+        external_id = external_id
+        internal_id = internal_id
+    }
+
+    // Constructs an item info that references a legacy object.
+    Legacy( int32 internal_id )
+    {
+        external_id = "legacy \"id\"\n"
+        internal_id = internal_id
+    }
+}
+
+message ObjectIdList
+{
+    // Constructs a list from the given object IDs.
+    Create( repeated ObjectId ids )
+    {
+        ids = ids
+    }
+}
+
+message ObjectIdMap
+{
+    // Constructs a map from the given object IDs.
+    Create( map<string, ObjectId> ids )
+    {
+        ids = ids
+    }
+}
+
+message ObjectData
+{
+    // Constructs object data from the given bytes.
+    Create( bytes data )
+    {
+        data = data
+    }
+
+    // Constructs object data with the magic header.
+    Magic()
+    {
+        data = hex"CAFEBABE"
+    }
+
+    // Constructs object data with a text header.
+    Header()
+    {
+        data = b"id\x00\xff"
+    }
+}
+
+message ObjectInfo
+{
+    // Constructs object info with the given owner.
+    Create( string owner )
+    {
+        owner = Owner::Create( owner )
+    }
+
+    // Constructs archived object info.
+    Archived(
+        // Owner name.
+        string owner,
+
+        // Object visibility.
+        Visibility visibility = Visibility::PRIVATE
+    )
+    {
+        owner = Owner::Create( owner )
+        status = ObjectStatus::OBJECT_STATUS_ARCHIVED
+        visibility = visibility
+    }
+
+    // Constructs object info with the creation time.
+    Created(
+        // Owner name.
+        string owner,
+
+        // Creation time.
+        google.protobuf.Timestamp created
+    )
+    {
+        owner = .jubjubnest.test.ObjectInfo.Owner::Create( owner )
+        visibility = ObjectInfo.Visibility::PUBLIC
+        created = created
+    }
+
+    message Owner
+    {
+        // Constructs an owner with the given name.
+        Create( string name )
+        {
+            name = name
+        }
+    }
+}
+
+message ObjectStats
+{
+    // Constructs statistics with values outside the 32-bit range.
+    Large( float ratio = 0.5 )
+    {
+        size = 3000000000
+        checksum = 4000000000
+        ratio = ratio
+        score = 2
+    }
+
+    // Constructs statistics with integer values for the floating point fields.
+    Unit()
+    {
+        ratio = 1
+        score = 1.5
+    }
+}
+
+message ObjectCounters
+{
+    // Constructs counters from the given values.
+    Create( map<string, int32> counters )
+    {
+        value = counters
+    }
 }
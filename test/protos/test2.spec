@@ -1,3 +1,12 @@
-
 package net.test
 
+message Struct
+{
+    // Constructs an archived struct.
+    Archived( int32 a, int32 b )
+    {
+        a = a
+        b = b
+        status = jubjubnest.test.ObjectStatus::OBJECT_STATUS_ARCHIVED
+    }
+}
@@ -0,0 +1,30 @@
+package jubjubnest.testing
+
+message ObjectId
+{
+    // Constructs an object ID with the given value.
+    Create( string value )
+    {
+        value = value
+    }
+}
+
+message Reference
+{
+    // Constructs a reference to each of the object IDs.
+    Create( int32 local, string shared, int32 type, int32 item_id )
+    {
+        local = ObjectId::Create( local )
+        shared = .jubjubnest.testing.ObjectId::Create( shared )
+        remote = test.ObjectId::Internal( type, item_id )
+    }
+
+    message ObjectId
+    {
+        // Constructs a local object ID with the given value.
+        Create( int32 value )
+        {
+            value = value
+        }
+    }
+}
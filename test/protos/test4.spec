@@ -0,0 +1,20 @@
+package jubjubnest.multi
+
+message Item
+{
+    // Constructs an item with a single part.
+    Create( string name, string part )
+    {
+        name = name
+        part = Part::Create( part )
+    }
+
+    message Part
+    {
+        // Constructs a part with the given name.
+        Create( string name )
+        {
+            name = name
+        }
+    }
+}